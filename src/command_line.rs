@@ -0,0 +1,414 @@
+//! Parsing of the commands given to `katac run -c` and of Makefile recipes, so they can be run
+//! without going through `make`.
+
+use std::process::Command;
+
+/// characters that only mean something to a shell, commands using them need `--shell`
+const SHELL_OPERATORS: [char; 10] = ['|', '&', ';', '<', '>', '(', ')', '`', '*', '?'];
+
+/// a command line split with POSIX shell quoting rules
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CommandLine {
+    /// leading `VAR=value` assignments
+    pub env: Vec<(String, String)>,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+/// a word of a command line, remembering where quoting started so `"A"=b` is not an assignment
+#[derive(Default)]
+struct Word {
+    text: String,
+    quoted_at: Option<usize>,
+}
+
+impl Word {
+    fn mark_quoted(&mut self) {
+        if self.quoted_at.is_none() {
+            self.quoted_at = Some(self.text.len());
+        }
+    }
+
+    /// returns the name and value if the word is a `VAR=value` assignment
+    fn assignment(&self) -> Option<(String, String)> {
+        let eq = self.text.find('=')?;
+        let name = &self.text[..eq];
+        let mut chars = name.chars();
+        let valid_name = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name || self.quoted_at.is_some_and(|q| q <= eq) {
+            return None;
+        }
+        Some((name.to_string(), self.text[eq + 1..].to_string()))
+    }
+}
+
+impl CommandLine {
+    /// parses a command line, failing on unterminated quotes and on syntax that needs a shell
+    /// (pipes, redirections, `&&`, globs, variable expansions, ...)
+    pub(crate) fn parse(line: &str) -> Result<CommandLine, String> {
+        let mut words = split_words(line)?.into_iter().peekable();
+
+        let mut env = Vec::new();
+        while let Some((name, value)) = words.peek().and_then(|w| w.assignment()) {
+            env.push((name, value));
+            words.next();
+        }
+
+        let program = match words.next() {
+            Some(word) => word.text,
+            None if env.is_empty() => return Err("empty command provided".to_string()),
+            None => return Err("no command after the variable assignments".to_string()),
+        };
+
+        Ok(CommandLine {
+            env,
+            program,
+            args: words.map(|w| w.text).collect(),
+        })
+    }
+
+    /// builds the command, with the assignments added to its environment
+    pub(crate) fn to_command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args)
+            .envs(self.env.iter().map(|(k, v)| (k, v)));
+        cmd
+    }
+}
+
+/// a custom command given to `katac run -c`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CustomCommand {
+    /// run directly, without a shell
+    Direct(CommandLine),
+    /// run through `sh -c` (`cmd /C` on Windows), with `--shell`
+    Shell(String),
+}
+
+impl CustomCommand {
+    pub(crate) fn parse(cmd_str: &str, shell: bool) -> Result<CustomCommand, String> {
+        if !shell {
+            return CommandLine::parse(cmd_str).map(CustomCommand::Direct);
+        }
+        if cmd_str.trim().is_empty() {
+            return Err("empty command provided".to_string());
+        }
+        Ok(CustomCommand::Shell(cmd_str.to_string()))
+    }
+
+    pub(crate) fn to_command(&self) -> Command {
+        match self {
+            CustomCommand::Direct(line) => line.to_command(),
+            CustomCommand::Shell(script) => shell_command(script),
+        }
+    }
+}
+
+/// builds a command that runs the script through the system shell
+pub(crate) fn shell_command(script: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(script);
+        return cmd;
+    }
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(script);
+    cmd
+}
+
+/// builds the command that runs a Makefile recipe. Single line recipes without shell syntax
+/// are run directly, the rest go through the shell and stop at the first failing line like
+/// make does.
+pub(crate) fn recipe_command(recipe: &[String]) -> Option<Command> {
+    match recipe {
+        [] => None,
+        [line] => Some(match CommandLine::parse(line) {
+            Ok(line) => line.to_command(),
+            Err(_) => shell_command(line),
+        }),
+        lines if cfg!(target_os = "windows") => Some(shell_command(&lines.join(" && "))),
+        lines => Some(shell_command(&format!("set -e\n{}", lines.join("\n")))),
+    }
+}
+
+/// extracts the recipe of the given target from a Makefile, one entry per command.
+/// Lines continued with a backslash are joined and make's `@`, `-` and `+` prefixes are
+/// removed (`-` lines have their errors ignored).
+pub(crate) fn makefile_recipe(content: &str, target: &str) -> Option<Vec<String>> {
+    let mut lines = content
+        .lines()
+        .skip_while(|line| !is_rule_for(line, target));
+    lines.next()?;
+
+    let mut recipe: Vec<String> = Vec::new();
+    let mut continued = false;
+    for line in lines {
+        if continued {
+            let last = recipe.last_mut()?;
+            last.push(' ');
+            last.push_str(line.trim_start());
+        } else if let Some(cmd) = line.strip_prefix('\t') {
+            recipe.push(cmd.trim().to_string());
+        } else if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        } else {
+            break;
+        }
+
+        continued = recipe.last().is_some_and(|cmd| cmd.ends_with('\\'));
+        if continued {
+            if let Some(last) = recipe.last_mut() {
+                last.pop();
+                let trimmed_len = last.trim_end().len();
+                last.truncate(trimmed_len);
+            }
+        }
+    }
+
+    let recipe: Vec<String> = recipe
+        .into_iter()
+        .filter_map(|cmd| {
+            let command = cmd.trim_start_matches(['@', '-', '+']);
+            let ignore_errors = cmd[..cmd.len() - command.len()].contains('-');
+            let command = command.trim_start().replace("$$", "$");
+            if command.is_empty() {
+                return None;
+            }
+            Some(if ignore_errors {
+                format!("{} || true", command)
+            } else {
+                command
+            })
+        })
+        .collect();
+
+    if recipe.is_empty() {
+        return None;
+    }
+    Some(recipe)
+}
+
+/// returns true if the line is a rule (`target: deps`) for the given target
+fn is_rule_for(line: &str, target: &str) -> bool {
+    if line.starts_with('\t') {
+        return false;
+    }
+    match line.split_once(':') {
+        Some((targets, rest)) if !rest.starts_with('=') => {
+            targets.split_whitespace().any(|t| t == target)
+        }
+        _ => false,
+    }
+}
+
+/// splits a command line into words following POSIX shell quoting rules
+fn split_words(line: &str) -> Result<Vec<Word>, String> {
+    let shell_syntax = |c: char| {
+        format!(
+            "'{}' is shell syntax, pass --shell to run the command through a shell",
+            c
+        )
+    };
+    let is_expansion = |next: Option<&char>| {
+        next.is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '{' | '('))
+    };
+
+    let mut words = Vec::new();
+    let mut word: Option<Word> = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(w) = word.take() {
+                    words.push(w);
+                }
+            }
+            '\'' => {
+                let w = word.get_or_insert_with(Word::default);
+                w.mark_quoted();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => w.text.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_with(Word::default);
+                w.mark_quoted();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => w.text.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                w.text.push('\\');
+                                w.text.push(c);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some('$') if is_expansion(chars.peek()) => return Err(shell_syntax('$')),
+                        Some('`') => return Err(shell_syntax('`')),
+                        Some(c) => w.text.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => {
+                    let w = word.get_or_insert_with(Word::default);
+                    w.mark_quoted();
+                    w.text.push(c);
+                }
+                None => word.get_or_insert_with(Word::default).text.push('\\'),
+            },
+            '$' if is_expansion(chars.peek()) => return Err(shell_syntax('$')),
+            '~' | '#' if word.is_none() => return Err(shell_syntax(c)),
+            c if SHELL_OPERATORS.contains(&c) => return Err(shell_syntax(c)),
+            c => word.get_or_insert_with(Word::default).text.push(c),
+        }
+    }
+
+    if let Some(w) = word {
+        words.push(w);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> CommandLine {
+        CommandLine::parse(line).unwrap()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        let line = parse("  go   test -v ");
+        assert_eq!(line.program, "go");
+        assert_eq!(line.args, ["test", "-v"]);
+        assert!(line.env.is_empty());
+    }
+
+    #[test]
+    fn single_quotes_are_literal() {
+        let line = parse("go test -run 'TestLRU$'");
+        assert_eq!(line.args, ["test", "-run", "TestLRU$"]);
+    }
+
+    #[test]
+    fn double_quotes_and_escapes() {
+        let line = parse(r#"echo "hello \"world\"" a\ b '' """#);
+        assert_eq!(line.args, ["hello \"world\"", "a b", "", ""]);
+    }
+
+    #[test]
+    fn leading_assignments_become_env() {
+        let line = parse("GOFLAGS=-count=1 CGO_ENABLED='0' go test");
+        assert_eq!(
+            line.env,
+            [
+                ("GOFLAGS".to_string(), "-count=1".to_string()),
+                ("CGO_ENABLED".to_string(), "0".to_string())
+            ]
+        );
+        assert_eq!(line.program, "go");
+        assert_eq!(line.args, ["test"]);
+    }
+
+    #[test]
+    fn assignments_after_the_program_are_arguments() {
+        let line = parse("make run V=1");
+        assert!(line.env.is_empty());
+        assert_eq!(line.args, ["run", "V=1"]);
+    }
+
+    #[test]
+    fn quoted_names_are_not_assignments() {
+        let line = parse(r#""A"=b run"#);
+        assert!(line.env.is_empty());
+        assert_eq!(line.program, "A=b");
+    }
+
+    #[test]
+    fn shell_syntax_is_rejected() {
+        for cmd in [
+            "go test | tee out",
+            "go vet && go test",
+            "echo $HOME",
+            "echo \"$(pwd)\"",
+            "ls *.go",
+            "cat < in",
+            "cd ~",
+        ] {
+            let err = CommandLine::parse(cmd).unwrap_err();
+            assert!(err.contains("--shell"), "{}: {}", cmd, err);
+        }
+    }
+
+    #[test]
+    fn errors_on_bad_input() {
+        assert!(CommandLine::parse("").is_err());
+        assert!(CommandLine::parse("echo 'oops").is_err());
+        assert!(CommandLine::parse("echo \"oops").is_err());
+        assert!(CommandLine::parse("FOO=bar").is_err());
+    }
+
+    #[test]
+    fn shell_mode_keeps_the_command_as_is() {
+        assert_eq!(
+            CustomCommand::parse("go vet && go test", true).unwrap(),
+            CustomCommand::Shell("go vet && go test".to_string())
+        );
+        assert!(CustomCommand::parse(" ", true).is_err());
+        assert!(CustomCommand::parse("go vet && go test", false).is_err());
+    }
+
+    #[test]
+    fn recipe_of_run_target() {
+        let makefile = "\
+CC := gcc
+
+build:
+\tgo build
+
+run: build
+\t@go vet ./...
+\t-rm -f out.txt
+\tgo test -v \\
+\t  -count=1
+
+clean:
+\trm -rf bin
+";
+        assert_eq!(
+            makefile_recipe(makefile, "run").unwrap(),
+            [
+                "go vet ./...",
+                "rm -f out.txt || true",
+                "go test -v -count=1"
+            ]
+        );
+        assert_eq!(makefile_recipe(makefile, "build").unwrap(), ["go build"]);
+        assert_eq!(makefile_recipe(makefile, "test"), None);
+        assert_eq!(makefile_recipe(makefile, "CC"), None);
+    }
+
+    #[test]
+    fn recipe_unescapes_dollars() {
+        let makefile = "run:\n\tgo test -run 'TestLRU$$'\n";
+        assert_eq!(
+            makefile_recipe(makefile, "run").unwrap(),
+            ["go test -run 'TestLRU$'"]
+        );
+    }
+}
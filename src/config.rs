@@ -0,0 +1,330 @@
+//! The config files of katac and how they are layered. The user config, e.g.
+//! `~/.config/katac/config.toml`, is merged under the project config, `./katac.toml` or
+//! `--config`: keys set in the project config win, `[kata.<name>]` tables are merged key by
+//! key and `[pools.<name>]` tables are replaced as a whole. Relative folders of the user
+//! config are relative to the folder of the user config, the ones of the project config to the
+//! current folder.
+
+use crate::{Data, Error};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// path of the user config, relative to the config folder of the user
+pub const USER_CONFIG_FILE: &str = "katac/config.toml";
+
+/// keys of [katas] that are paths
+const PATH_KEYS: [&str; 3] = ["katas_dir", "days_dir", "history_file"];
+
+/// where the effective value of a config key comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// the default of katac
+    Default,
+    /// the user config file
+    User(PathBuf),
+    /// the config file of the project, ./katac.toml or --config
+    Project(PathBuf),
+    /// an environment variable
+    Env(&'static str),
+    /// a command line argument
+    Arg(&'static str),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::User(path) => write!(f, "user config {}", path.display()),
+            Origin::Project(path) => write!(f, "project config {}", path.display()),
+            Origin::Env(var) => write!(f, "env {}", var),
+            Origin::Arg(arg) => write!(f, "{}", arg),
+        }
+    }
+}
+
+/// returns the path of the user config: under $XDG_CONFIG_HOME, or ~/.config (%APPDATA% on
+/// Windows) when it isn't set. None if there is no home folder
+pub fn user_config_path() -> Option<PathBuf> {
+    let non_empty = |var| std::env::var_os(var).filter(|v| !v.is_empty());
+    let config_home = non_empty("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| match cfg!(target_os = "windows") {
+            true => non_empty("APPDATA").map(PathBuf::from),
+            false => non_empty("HOME").map(|home| Path::new(&home).join(".config")),
+        })?;
+    Some(config_home.join(USER_CONFIG_FILE))
+}
+
+/// the config files merged into one, with where every value comes from
+#[derive(Debug, Default)]
+pub(crate) struct Layers {
+    table: toml::Table,
+    /// origin of every value, by the dotted path of its key, e.g. `katas.timeout`
+    origins: BTreeMap<String, Origin>,
+}
+
+impl Layers {
+    /// merges the given config files in order, each one over the previous ones. Files that
+    /// don't exist are skipped
+    pub(crate) fn load(files: &[(PathBuf, Origin)]) -> Result<Layers, Error> {
+        let mut layers = Layers::default();
+        for (path, origin) in files {
+            if let Some(mut table) = read_layer(path)? {
+                if let (Origin::User(_), Some(folder)) = (origin, path.parent()) {
+                    resolve_paths(&mut table, folder);
+                }
+                merge(&mut layers.table, table, "", origin, &mut layers.origins);
+            }
+        }
+        Ok(layers)
+    }
+
+    /// returns the merged config
+    pub(crate) fn data(&self) -> Result<Data, Error> {
+        Data::deserialize(self.table.clone()).map_err(|e| Error::ConfigParse {
+            path: PathBuf::from("the merged config"),
+            message: e.message().to_string(),
+        })
+    }
+
+    /// sets the effective value of a key of a table, e.g. a folder given with an env var
+    pub(crate) fn set(&mut self, table: &str, key: &str, value: toml::Value, origin: Origin) {
+        let entry = self
+            .table
+            .entry(table)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let toml::Value::Table(entry) = entry {
+            entry.insert(key.to_string(), value);
+            self.origins.insert(format!("{}.{}", table, key), origin);
+        }
+    }
+
+    /// sets the default value of a key of a table, unless a config file sets it
+    pub(crate) fn set_default(&mut self, table: &str, key: &str, value: toml::Value) {
+        if !self.origins.contains_key(&format!("{}.{}", table, key)) {
+            self.set(table, key, value, Origin::Default);
+        }
+    }
+
+    /// returns whether a config file, an env var or an argument set the dotted key
+    pub(crate) fn is_set(&self, key: &str) -> bool {
+        self.origins
+            .get(key)
+            .is_some_and(|origin| *origin != Origin::Default)
+    }
+
+    /// returns every value with its key and origin, sorted by key
+    pub(crate) fn entries(&self) -> Vec<(&str, &toml::Value, &Origin)> {
+        self.origins
+            .iter()
+            .filter_map(|(key, origin)| Some((key.as_str(), self.get(key)?, origin)))
+            .collect()
+    }
+
+    /// returns the value of a dotted key
+    fn get(&self, key: &str) -> Option<&toml::Value> {
+        let mut parts = key.split('.');
+        let mut value = self.table.get(parts.next()?)?;
+        for part in parts {
+            value = value.as_table()?.get(part)?;
+        }
+        Some(value)
+    }
+}
+
+/// reads a config file as a table, None if it doesn't exist. Fails if it isn't a valid config
+fn read_layer(path: &Path) -> Result<Option<toml::Table>, Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path).map_err(Error::io(format!(
+        "failed to read the config file {}",
+        path.display()
+    )))?;
+    let parse_error = |message: &str| Error::ConfigParse {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    };
+    let table: toml::Table = toml::from_str(&content).map_err(|e| parse_error(e.message()))?;
+    Data::deserialize(table.clone()).map_err(|e| parse_error(e.message()))?;
+    Ok(Some(table))
+}
+
+/// makes the relative paths of the [katas] table relative to the given folder instead of the
+/// current one
+fn resolve_paths(table: &mut toml::Table, folder: &Path) {
+    let Some(toml::Value::Table(katas)) = table.get_mut("katas") else {
+        return;
+    };
+    for key in PATH_KEYS {
+        if let Some(toml::Value::String(path)) = katas.get_mut(key) {
+            if Path::new(path.as_str()).is_relative() {
+                *path = folder.join(&*path).to_string_lossy().into_owned();
+            }
+        }
+    }
+}
+
+/// returns whether a table is replaced as a whole instead of merged key by key
+fn replaced_whole(key: &str) -> bool {
+    key.strip_prefix("pools.")
+        .is_some_and(|pool| !pool.is_empty())
+}
+
+/// merges a layer into the table, recording the origin of every value it sets
+fn merge(
+    table: &mut toml::Table,
+    layer: toml::Table,
+    prefix: &str,
+    origin: &Origin,
+    origins: &mut BTreeMap<String, Origin>,
+) {
+    for (key, value) in layer {
+        let path = match prefix {
+            "" => key.clone(),
+            prefix => format!("{}.{}", prefix, key),
+        };
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(value))
+                if !replaced_whole(&path) =>
+            {
+                merge(existing, value, &path, origin, origins);
+            }
+            (_, value) => {
+                origins.retain(|k, _| k != &path && !k.starts_with(&format!("{}.", path)));
+                record_origins(&value, &path, origin, origins);
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+/// records the origin of the value and, for tables, of every value inside
+fn record_origins(
+    value: &toml::Value,
+    path: &str,
+    origin: &Origin,
+    origins: &mut BTreeMap<String, Origin>,
+) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                record_origins(value, &format!("{}.{}", path, key), origin, origins);
+            }
+        }
+        _ => {
+            origins.insert(path.to_string(), origin.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn write_configs(name: &str, user: &str, project: &str) -> (TempDir, Vec<(PathBuf, Origin)>) {
+        let dir = TempDir::new(&format!("config_{}", name));
+        let (user_path, project_path) = (dir.join("config.toml"), dir.join("katac.toml"));
+        fs::write(&user_path, user).unwrap();
+        fs::write(&project_path, project).unwrap();
+        let files = vec![
+            (user_path.clone(), Origin::User(user_path)),
+            (project_path.clone(), Origin::Project(project_path)),
+        ];
+        (dir, files)
+    }
+
+    #[test]
+    fn project_config_wins() {
+        let (_dir, files) = write_configs(
+            "layers",
+            r#"
+[katas]
+timeout = 10
+editor = "vim"
+
+[kata.LRU]
+timeout = 5
+parser = "go-test"
+
+[pools.weekday]
+katas = ["LRU", "Trie"]
+exclude_days = 2
+"#,
+            r#"
+[katas]
+timeout = 30
+
+[kata.LRU]
+timeout = 60
+
+[pools.weekday]
+katas = ["Heap"]
+"#,
+        );
+        let layers = Layers::load(&files).unwrap();
+        let (user, project) = (&files[0].1, &files[1].1);
+        let origins: Vec<(&str, String, &Origin)> = layers
+            .entries()
+            .into_iter()
+            .map(|(key, value, origin)| (key, value.to_string(), origin))
+            .collect();
+        assert_eq!(
+            origins,
+            [
+                ("kata.LRU.parser", "\"go-test\"".to_string(), user),
+                ("kata.LRU.timeout", "60".to_string(), project),
+                ("katas.editor", "\"vim\"".to_string(), user),
+                ("katas.timeout", "30".to_string(), project),
+                ("pools.weekday.katas", "[\"Heap\"]".to_string(), project),
+            ]
+        );
+
+        let data = layers.data().unwrap();
+        assert_eq!(data.katas.timeout, Some(30));
+        assert_eq!(data.pools["weekday"].exclude_days, None);
+    }
+
+    #[test]
+    fn defaults_and_overrides() {
+        let (_dir, files) = write_configs("defaults", "[katas]\ndays_dir = \"my_days\"\n", "");
+        let mut layers = Layers::load(&files).unwrap();
+        layers.set_default("katas", "days_dir", "days".into());
+        layers.set_default("katas", "katas_dir", "katas".into());
+        assert_eq!(layers.entries()[0].2, &files[0].1);
+        assert_eq!(layers.entries()[1].2, &Origin::Default);
+
+        layers.set("katas", "days_dir", "other".into(), Origin::Env("DAYS_DIR"));
+        assert_eq!(
+            layers.entries()[0],
+            ("katas.days_dir", &"other".into(), &Origin::Env("DAYS_DIR"))
+        );
+    }
+
+    #[test]
+    fn user_paths_are_relative_to_the_user_config() {
+        let (dir, files) = write_configs(
+            "paths",
+            "[katas]\ndays_dir = \"days\"\nkatas_dir = \"/katas\"\nhistory_file = \"h.jsonl\"\n",
+            "[katas]\nhistory_file = \"history.jsonl\"\n",
+        );
+        let data = Layers::load(&files).unwrap().data().unwrap();
+        let days_dir = dir.join("days").to_string_lossy().into_owned();
+        assert_eq!(data.katas.days_dir, Some(days_dir));
+        assert_eq!(data.katas.katas_dir.as_deref(), Some("/katas"));
+        assert_eq!(data.katas.history_file.as_deref(), Some("history.jsonl"));
+    }
+
+    #[test]
+    fn invalid_layer() {
+        let (_dir, files) = write_configs("invalid", "[katas]\ntimeout = \"soon\"\n", "");
+        match Layers::load(&files) {
+            Err(Error::ConfigParse { path, .. }) => assert_eq!(path, files[0].0),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Day folders of a workspace and how they are named: `day3`, `2026-10-17` or `2026-W42/mon`.
+//!
+//! Every day has a number, used in the history. With the `numbered` scheme it's the N of
+//! `dayN`, with the date based schemes it's the date as YYYYMMDD, so it doesn't change when
+//! other day folders are deleted.
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// how the day folders are named
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DayScheme {
+    /// day1, day2, ...
+    #[default]
+    Numbered,
+    /// the ISO date of the day, e.g. 2026-10-17
+    Date,
+    /// the ISO year and week, and the weekday, e.g. 2026-W42/sat
+    Week,
+}
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// returns the number of a day of a date based scheme
+pub fn date_number(date: NaiveDate) -> u32 {
+    date.year() as u32 * 10000 + date.month() * 100 + date.day()
+}
+
+/// returns the date of a day of a date based scheme, None if the number isn't a date
+pub fn number_date(number: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt((number / 10000) as i32, number / 100 % 100, number % 100)
+}
+
+/// the days folder of a workspace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Days {
+    dir: PathBuf,
+    scheme: DayScheme,
+    /// date new days start on with the date based schemes
+    today: NaiveDate,
+}
+
+impl Days {
+    pub fn new(dir: impl Into<PathBuf>, scheme: DayScheme, today: NaiveDate) -> Self {
+        Days {
+            dir: dir.into(),
+            scheme,
+            today,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn scheme(&self) -> DayScheme {
+        self.scheme
+    }
+
+    /// returns the name of the folder of the day, relative to the days folder. Days that don't
+    /// fit the scheme, e.g. recorded in the history before a migration, are named `dayN`
+    pub fn name(&self, day: u32) -> String {
+        let date = match self.scheme {
+            DayScheme::Numbered => None,
+            DayScheme::Date | DayScheme::Week => number_date(day),
+        };
+        match (self.scheme, date) {
+            (DayScheme::Date, Some(date)) => date.format("%Y-%m-%d").to_string(),
+            (DayScheme::Week, Some(date)) => format!(
+                "{}-W{:02}/{}",
+                date.iso_week().year(),
+                date.iso_week().week(),
+                WEEKDAYS[date.weekday().num_days_from_monday() as usize]
+            ),
+            _ => format!("day{}", day),
+        }
+    }
+
+    /// returns the path of the day
+    pub fn path(&self, day: u32) -> PathBuf {
+        self.dir.join(self.name(day))
+    }
+
+    /// parses the name of a day folder, or a day given on the command line. A bare number is
+    /// accepted for `dayN`
+    pub fn parse(&self, name: &str) -> Option<u32> {
+        match self.scheme {
+            DayScheme::Numbered => name
+                .strip_prefix("day")
+                .unwrap_or(name)
+                .parse::<u32>()
+                .ok()
+                .filter(|&day| day > 0),
+            DayScheme::Date => NaiveDate::parse_from_str(name, "%Y-%m-%d")
+                .ok()
+                .filter(|date| date.format("%Y-%m-%d").to_string() == name)
+                .map(date_number),
+            DayScheme::Week => {
+                let (week, weekday) = name.split_once('/')?;
+                let (year, week) = week.split_once("-W")?;
+                let weekday = WEEKDAYS.iter().position(|&d| d == weekday)?;
+                NaiveDate::from_isoywd_opt(
+                    year.parse().ok()?,
+                    week.parse().ok()?,
+                    Weekday::try_from(weekday as u8).ok()?,
+                )
+                .map(date_number)
+                .filter(|&day| self.name(day) == name)
+            }
+        }
+    }
+
+    /// returns the number and path of every day folder, by day
+    pub fn days(&self) -> Vec<(u32, PathBuf)> {
+        let mut days: Vec<(u32, PathBuf)> = match self.scheme {
+            DayScheme::Numbered | DayScheme::Date => subdirs(&self.dir)
+                .into_iter()
+                .filter_map(|(name, path)| Some((self.parse(&name)?, path)))
+                .collect(),
+            DayScheme::Week => subdirs(&self.dir)
+                .into_iter()
+                .flat_map(|(week, path)| {
+                    subdirs(&path)
+                        .into_iter()
+                        .map(move |(weekday, path)| (format!("{}/{}", week, weekday), path))
+                })
+                .filter_map(|(name, path)| Some((self.parse(&name)?, path)))
+                .collect(),
+        };
+        days.sort();
+        days
+    }
+
+    /// returns the number of the latest day, 0 if there are no days
+    pub fn current(&self) -> u32 {
+        self.days().last().map(|(day, _)| *day).unwrap_or(0)
+    }
+
+    /// returns the path of the latest day
+    pub fn current_path(&self) -> PathBuf {
+        self.path(self.current())
+    }
+
+    /// returns the day a new day starts on: the one after the latest with `dayN`, today with
+    /// the date based schemes
+    pub fn next(&self) -> u32 {
+        match self.scheme {
+            DayScheme::Numbered => self.current() + 1,
+            DayScheme::Date | DayScheme::Week => date_number(self.today),
+        }
+    }
+
+    /// returns the latest day, or the day a new day starts on if there are no days yet
+    pub fn current_or_next(&self) -> u32 {
+        match self.current() {
+            0 => self.next(),
+            day => day,
+        }
+    }
+}
+
+/// returns the name and path of the folders in a folder
+fn subdirs(dir: &Path) -> Vec<(String, PathBuf)> {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| Some((e.file_name().into_string().ok()?, e.path())))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn days(scheme: DayScheme) -> Days {
+        // a saturday
+        Days::new("days", scheme, date("2026-10-17"))
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(days(DayScheme::Numbered).name(3), "day3");
+        assert_eq!(days(DayScheme::Date).name(20261017), "2026-10-17");
+        assert_eq!(days(DayScheme::Week).name(20261017), "2026-W42/sat");
+        // the ISO year of a week can differ from the year of its dates
+        assert_eq!(days(DayScheme::Week).name(20270101), "2026-W53/fri");
+        assert_eq!(days(DayScheme::Week).name(20241230), "2025-W01/mon");
+        // recorded before a migration
+        assert_eq!(days(DayScheme::Date).name(3), "day3");
+        assert_eq!(
+            days(DayScheme::Date).path(20261017),
+            PathBuf::from("days/2026-10-17")
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let numbered = days(DayScheme::Numbered);
+        assert_eq!(numbered.parse("day12"), Some(12));
+        assert_eq!(numbered.parse("12"), Some(12));
+        assert_eq!(numbered.parse("day0"), None);
+        assert_eq!(numbered.parse("2026-10-17"), None);
+
+        let date = days(DayScheme::Date);
+        assert_eq!(date.parse("2026-10-17"), Some(20261017));
+        assert_eq!(date.parse("2026-02-30"), None);
+        assert_eq!(date.parse("day3"), None);
+
+        let week = days(DayScheme::Week);
+        assert_eq!(week.parse("2026-W42/mon"), Some(20261012));
+        assert_eq!(week.parse("2025-W43/mon"), Some(20251020));
+        assert_eq!(week.parse("2026-W53/fri"), Some(20270101));
+        assert_eq!(week.parse("2025-W01/mon"), Some(20241230));
+        assert_eq!(week.parse("2026-W42/someday"), None);
+        assert_eq!(week.parse("2026-W60/mon"), None);
+        assert_eq!(week.parse("2026-W5/mon"), None);
+        assert_eq!(week.parse("week42/mon"), None);
+    }
+
+    #[test]
+    fn next_day() {
+        assert_eq!(days(DayScheme::Date).next(), 20261017);
+        assert_eq!(days(DayScheme::Week).next(), 20261017);
+        let dir = TempDir::new("days");
+        let numbered = Days::new(dir.to_path_buf(), DayScheme::Numbered, date("2026-10-17"));
+        assert_eq!(numbered.current(), 0);
+        assert_eq!(numbered.current_or_next(), 1);
+
+        fs::create_dir_all(dir.join("day2")).unwrap();
+        fs::create_dir_all(dir.join("day10")).unwrap();
+        fs::create_dir_all(dir.join("notes")).unwrap();
+        assert_eq!(numbered.current(), 10);
+        assert_eq!(numbered.next(), 11);
+    }
+
+    #[test]
+    fn week_days() {
+        let dir = TempDir::new("week_days");
+        fs::create_dir_all(dir.join("2025-W52/sun")).unwrap();
+        fs::create_dir_all(dir.join("2026-W41/fri")).unwrap();
+        fs::create_dir_all(dir.join("2026-W42/mon")).unwrap();
+        let week = Days::new(dir.to_path_buf(), DayScheme::Week, date("2026-10-17"));
+        let days: Vec<u32> = week.days().into_iter().map(|(day, _)| day).collect();
+        assert_eq!(days, [20251228, 20261009, 20261012]);
+        assert_eq!(week.current_path(), dir.join("2026-W42/mon"));
+    }
+}
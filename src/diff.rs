@@ -0,0 +1,243 @@
+//! Unified diffs of a kata between two days, or between a day and the kata's template.
+
+use crate::ARTIFACT_DIRS;
+use clap::ValueEnum;
+use similar::TextDiff;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// lines of unchanged context around every change
+const CONTEXT_LINES: usize = 3;
+/// extensions of compiled files, never diffed
+const ARTIFACT_EXTENSIONS: [&str; 6] = ["o", "class", "pyc", "hi", "exe", "beam"];
+
+/// when the diff is colored
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// when printing to a terminal and NO_COLOR isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// a folder being diffed, and the name its files are shown with, e.g. `day3/LRU`
+pub struct DiffSide<'a> {
+    pub dir: &'a Path,
+    pub label: &'a str,
+    /// file or folder of `dir` left out of the diff, e.g. the solution of a template
+    pub excluded: Option<&'a Path>,
+}
+
+impl DiffSide<'_> {
+    /// returns the files of the side, relative to its folder
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files = source_files(self.dir, Path::new(""));
+        if let Some(excluded) = self.excluded {
+            files.retain(|file| !file.starts_with(excluded));
+        }
+        files
+    }
+}
+
+/// returns the unified diff of every file that differs between the folders, sorted by path.
+/// Files only in one of them are diffed against `/dev/null`
+pub fn diff_dirs(old: &DiffSide, new: &DiffSide) -> String {
+    let (old_files, new_files) = (old.files(), new.files());
+    let files: BTreeSet<&PathBuf> = old_files.iter().chain(&new_files).collect();
+
+    let mut diff = String::new();
+    for file in files {
+        let read = |side: &DiffSide, files: &[PathBuf]| match files.contains(file) {
+            true => fs::read(side.dir.join(file)).ok(),
+            false => None,
+        };
+        let old_content = read(old, &old_files);
+        let new_content = read(new, &new_files);
+        if old_content == new_content {
+            continue;
+        }
+        let header = |side: &DiffSide, content: &Option<Vec<u8>>| match content {
+            Some(_) => format!("{}/{}", side.label, file.display()),
+            None => "/dev/null".to_string(),
+        };
+        let (old_header, new_header) = (header(old, &old_content), header(new, &new_content));
+
+        let as_text = |content: &Option<Vec<u8>>| match content {
+            Some(bytes) => String::from_utf8(bytes.clone()).ok(),
+            None => Some(String::new()),
+        };
+        match (as_text(&old_content), as_text(&new_content)) {
+            (Some(old_text), Some(new_text)) => diff.push_str(
+                &TextDiff::from_lines(&old_text, &new_text)
+                    .unified_diff()
+                    .context_radius(CONTEXT_LINES)
+                    .header(&old_header, &new_header)
+                    .to_string(),
+            ),
+            _ => diff.push_str(&format!(
+                "Binary files {} and {} differ\n",
+                old_header, new_header
+            )),
+        }
+    }
+    diff
+}
+
+/// returns the files inside dir, relative to it, skipping build artifacts
+fn source_files(dir: &Path, relative: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir.join(relative)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = relative.join(entry.file_name());
+        if entry.path().is_dir() {
+            if !ARTIFACT_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()) {
+                files.extend(source_files(dir, &path));
+            }
+        } else if !path
+            .extension()
+            .is_some_and(|ext| ARTIFACT_EXTENSIONS.contains(&ext.to_string_lossy().as_ref()))
+        {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// colors a unified diff: headers in bold, hunk ranges in cyan, removed lines in red and added
+/// lines in green
+pub fn colorize(diff: &str) -> String {
+    diff.split_inclusive('\n')
+        .map(|line| {
+            let color = if line.starts_with("---") || line.starts_with("+++") {
+                "1"
+            } else if line.starts_with("@@") {
+                "36"
+            } else if line.starts_with('-') {
+                "31"
+            } else if line.starts_with('+') {
+                "32"
+            } else {
+                return line.to_string();
+            };
+            let (text, newline) = match line.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (line, ""),
+            };
+            format!("\x1b[{}m{}\x1b[0m{}", color, text, newline)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn diffs_changed_added_and_removed_files() {
+        let old = TempDir::with_files(
+            "diff_old",
+            &[
+                ("lru.go", "package lru\n\nfunc Get() {}\n"),
+                ("notes.txt", "todo\n"),
+                ("target/debug/lru", "old build"),
+            ],
+        );
+        let new = TempDir::with_files(
+            "diff_new",
+            &[
+                ("lru.go", "package lru\n\nfunc Get() int { return 0 }\n"),
+                ("src/put.go", "package lru\n"),
+                ("target/debug/lru", "new build"),
+                ("lru.o", "object"),
+            ],
+        );
+        let diff = diff_dirs(
+            &DiffSide {
+                dir: &old,
+                label: "day1/LRU",
+                excluded: None,
+            },
+            &DiffSide {
+                dir: &new,
+                label: "day2/LRU",
+                excluded: None,
+            },
+        );
+        assert_eq!(
+            diff,
+            "--- day1/LRU/lru.go\n+++ day2/LRU/lru.go\n@@ -1,3 +1,3 @@\n package lru\n \n\
+             -func Get() {}\n+func Get() int { return 0 }\n\
+             --- day1/LRU/notes.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-todo\n\
+             --- /dev/null\n+++ day2/LRU/src/put.go\n@@ -0,0 +1 @@\n+package lru\n"
+        );
+    }
+
+    #[test]
+    fn same_folders_have_no_diff() {
+        let dir = TempDir::with_files("diff_same", &[("main.go", "package main\n")]);
+        let side = DiffSide {
+            dir: &dir,
+            label: "day1/foo",
+            excluded: None,
+        };
+        assert_eq!(diff_dirs(&side, &side), "");
+    }
+
+    #[test]
+    fn excluded_files_are_not_diffed() {
+        let template = TempDir::with_files(
+            "diff_template",
+            &[
+                (
+                    "main.go",
+                    "package main
+",
+                ),
+                (
+                    "solution/main.go",
+                    "package main
+
+func solved() {}
+",
+                ),
+            ],
+        );
+        let day = TempDir::with_files(
+            "diff_day",
+            &[(
+                "main.go",
+                "package main
+",
+            )],
+        );
+        let diff = diff_dirs(
+            &DiffSide {
+                dir: &template,
+                label: "template/foo",
+                excluded: Some(Path::new("solution")),
+            },
+            &DiffSide {
+                dir: &day,
+                label: "day1/foo",
+                excluded: None,
+            },
+        );
+        assert_eq!(diff, "");
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(
+            colorize("--- a\n+++ b\n@@ -1 +1 @@\n-old\n+new\n same"),
+            "\x1b[1m--- a\x1b[0m\n\x1b[1m+++ b\x1b[0m\n\x1b[36m@@ -1 +1 @@\x1b[0m\n\
+             \x1b[31m-old\x1b[0m\n\x1b[32m+new\x1b[0m\n same"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Errors of the katac operations. The library never prints them nor exits, the caller decides
+//! how to report them. The errors of operations that partly succeeded carry what was done.
+
+use crate::{CopyRecord, DayMove, DayScheme, InitializedKata};
+use clap::ValueEnum;
+use std::fmt;
+use std::path::PathBuf;
+
+/// why a katac operation failed
+#[derive(Debug)]
+pub enum Error {
+    /// the kata isn't where it was looked for, e.g. the katas folder or a day
+    KataNotFound { kata: String, location: String },
+    /// a kata name that can't be used
+    InvalidName { name: String, reason: &'static str },
+    /// the kata to create is already in the katas folder
+    KataExists { kata: String, location: String },
+    /// the kata is already in the day it is copied to
+    AlreadyInDay { kata: String, day: String },
+    /// a day that doesn't fit the day scheme, with an example of one that does
+    InvalidDay {
+        day: String,
+        scheme: DayScheme,
+        example: String,
+    },
+    /// the config file can't be read or parsed
+    ConfigParse { path: PathBuf, message: String },
+    /// a file or folder can't be read or written
+    Io {
+        context: String,
+        source: std::io::Error,
+    },
+    /// katas failed their run, the summary of the run has the details
+    RunFailed { katas: Vec<String> },
+    /// some katas couldn't be copied to a day, the others were
+    CopyFailed {
+        copied: Vec<CopyRecord>,
+        errors: Vec<Error>,
+    },
+    /// some templates couldn't be copied to the katas folder, the others were
+    InitFailed {
+        initialized: Vec<InitializedKata>,
+        errors: Vec<Error>,
+    },
+    /// some days couldn't be migrated, the others were
+    MigrateFailed {
+        moved: Vec<DayMove>,
+        errors: Vec<Error>,
+    },
+    /// katac random can't pick from the pool
+    Pool(String),
+    /// invalid arguments, e.g. a bad --command or a file outside the kata
+    InvalidInput(String),
+    /// the user cancelled an interactive prompt
+    Cancelled,
+    /// the new release couldn't be downloaded or installed
+    Upgrade(String),
+}
+
+impl Error {
+    /// returns a function that wraps an io error with what was being done
+    pub(crate) fn io(context: impl Into<String>) -> impl FnOnce(std::io::Error) -> Error {
+        let context = context.into();
+        move |source| Error::Io { context, source }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::KataNotFound { kata, location } => {
+                write!(f, "'{}' is not in {}", kata, location)
+            }
+            Error::InvalidName { name, reason } => {
+                write!(f, "kata name '{}' {}", name, reason)
+            }
+            Error::KataExists { kata, location } => {
+                write!(f, "kata '{}' already exists in {}", kata, location)
+            }
+            Error::AlreadyInDay { kata, day } => write!(
+                f,
+                "'{}' is already in {}, pass --force to replace it",
+                kata, day
+            ),
+            Error::InvalidDay {
+                day,
+                scheme,
+                example,
+            } => write!(
+                f,
+                "'{}' is not a day of the {} day folders, e.g. {}",
+                day,
+                scheme.to_possible_value().unwrap().get_name(),
+                example
+            ),
+            Error::ConfigParse { path, message } => {
+                write!(f, "invalid config file {}: {}", path.display(), message)
+            }
+            Error::Io { context, source } => write!(f, "{}: {}", context, source),
+            Error::RunFailed { katas } => {
+                write!(f, "{} kata(s) failed: {}", katas.len(), katas.join(", "))
+            }
+            Error::CopyFailed { errors, .. } | Error::InitFailed { errors, .. } => {
+                write!(f, "failed to copy {} kata(s)", errors.len())
+            }
+            Error::MigrateFailed { errors, .. } => {
+                write!(f, "failed to migrate {} day(s)", errors.len())
+            }
+            Error::Pool(message) | Error::InvalidInput(message) => write!(f, "{}", message),
+            Error::Cancelled => write!(f, "cancelled"),
+            Error::Upgrade(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages() {
+        assert_eq!(
+            Error::AlreadyInDay {
+                kata: "foo".to_string(),
+                day: "day1".to_string()
+            }
+            .to_string(),
+            "'foo' is already in day1, pass --force to replace it"
+        );
+        assert_eq!(
+            Error::InvalidDay {
+                day: "monday".to_string(),
+                scheme: DayScheme::Date,
+                example: "2026-10-17".to_string()
+            }
+            .to_string(),
+            "'monday' is not a day of the date day folders, e.g. 2026-10-17"
+        );
+        let io = Error::io("failed to read katas")(std::io::Error::other("denied"));
+        assert_eq!(io.to_string(), "failed to read katas: denied");
+        assert!(std::error::Error::source(&io).is_some());
+    }
+}
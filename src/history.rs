@@ -0,0 +1,219 @@
+//! History of the katas practiced in a workspace. Every `start`, `random` and `run` appends
+//! one json record per kata to `.katac/history.jsonl`, so stats and schedules can look further
+//! back than the `days/dayN` folders.
+
+use chrono::{DateTime, FixedOffset, Local};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// default location of the history, relative to the workspace
+pub const HISTORY_FILE: &str = ".katac/history.jsonl";
+
+/// command that produced a history record
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryEvent {
+    /// the kata was copied to a new day with `katac start` or `katac <kata>`
+    Start,
+    /// the kata was copied to a new day with `katac random`
+    Random,
+    /// the kata was run with `katac run`
+    Run,
+}
+
+/// a single line of the history
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryRecord {
+    /// local time of the event, with its UTC offset
+    pub timestamp: DateTime<FixedOffset>,
+    pub event: HistoryEvent,
+    pub day: u32,
+    pub kata: String,
+    #[serde(default)]
+    pub language: Option<String>,
+    /// whether the run passed, None for start and random
+    #[serde(default)]
+    pub passed: Option<bool>,
+    /// how long the run took, None for start and random
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+}
+
+impl HistoryRecord {
+    /// returns a record of the event happening now
+    pub fn new(event: HistoryEvent, day: u32, kata: &str, language: Option<String>) -> Self {
+        HistoryRecord {
+            timestamp: Local::now().fixed_offset(),
+            event,
+            day,
+            kata: kata.to_string(),
+            language,
+            passed: None,
+            duration_ms: None,
+        }
+    }
+
+    /// returns a record of a kata run that just finished
+    pub fn run(
+        day: u32,
+        kata: &str,
+        language: Option<String>,
+        passed: bool,
+        duration: Duration,
+    ) -> Self {
+        HistoryRecord {
+            passed: Some(passed),
+            duration_ms: Some(duration.as_millis() as u64),
+            ..HistoryRecord::new(HistoryEvent::Run, day, kata, language)
+        }
+    }
+}
+
+/// the history file of a workspace
+#[derive(Debug, Clone)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        History { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// appends the records to the history, creating it if needed
+    pub fn append(&self, records: &[HistoryRecord]) -> std::io::Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        self.create_parent()?;
+        // a single write, so runs of different katac processes don't interleave their lines
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(to_lines(records)?.as_bytes())
+    }
+
+    /// replaces the history with the records, writing them to a temporary file first so a
+    /// crash never leaves the history half written
+    pub fn write(&self, records: &[HistoryRecord]) -> std::io::Result<()> {
+        self.create_parent()?;
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, to_lines(records)?)?;
+        fs::rename(&tmp, &self.path)
+    }
+
+    fn create_parent(&self) -> std::io::Result<()> {
+        match self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            Some(parent) => fs::create_dir_all(parent),
+            None => Ok(()),
+        }
+    }
+
+    /// returns every record of the history, oldest first. A missing history is empty, and lines
+    /// that can't be parsed (e.g. cut short by a crash) are skipped
+    pub fn read(&self) -> std::io::Result<Vec<HistoryRecord>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        Ok(content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(i, line)| match serde_json::from_str(line) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    warn!("skipping line {} of {}: {}", i + 1, self.path.display(), e);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+/// returns the records as json lines
+fn to_lines(records: &[HistoryRecord]) -> std::io::Result<String> {
+    let mut lines = String::new();
+    for record in records {
+        lines.push_str(&serde_json::to_string(record)?);
+        lines.push('\n');
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn temp_history(name: &str) -> (TempDir, History) {
+        let dir = TempDir::new(&format!("history_{}", name));
+        let history = History::new(dir.join(".katac/history.jsonl"));
+        (dir, history)
+    }
+
+    #[test]
+    fn missing_history_is_empty() {
+        let (_dir, history) = temp_history("missing");
+        assert_eq!(history.read().unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn append_and_read_back() {
+        let (_dir, history) = temp_history("append");
+        let start = HistoryRecord::new(HistoryEvent::Start, 1, "LRU", Some("go".to_string()));
+        let run = HistoryRecord::run(1, "LRU", None, false, Duration::from_millis(1234));
+        history.append(std::slice::from_ref(&start)).unwrap();
+        history.append(std::slice::from_ref(&run)).unwrap();
+
+        assert_eq!(history.read().unwrap(), vec![start, run]);
+    }
+
+    #[test]
+    fn write_replaces_the_history() {
+        let (_dir, history) = temp_history("write");
+        let start = HistoryRecord::new(HistoryEvent::Start, 1, "LRU", None);
+        history.append(std::slice::from_ref(&start)).unwrap();
+        let renumbered = HistoryRecord {
+            day: 20240301,
+            ..start
+        };
+        history.write(std::slice::from_ref(&renumbered)).unwrap();
+
+        assert_eq!(history.read().unwrap(), vec![renumbered]);
+    }
+
+    #[test]
+    fn skips_broken_lines() {
+        let (_dir, history) = temp_history("broken");
+        let record = HistoryRecord::new(HistoryEvent::Random, 2, "Trie", None);
+        history.append(std::slice::from_ref(&record)).unwrap();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(history.path())
+            .unwrap();
+        file.write_all(b"{\"timestamp\":\"2024-").unwrap();
+
+        assert_eq!(history.read().unwrap(), vec![record]);
+    }
+
+    #[test]
+    fn record_format() {
+        let line = r#"{"timestamp":"2024-03-01T09:30:00+01:00","event":"run","day":3,"kata":"LRU","language":"go","passed":true,"duration_ms":812}"#;
+        let record: HistoryRecord = serde_json::from_str(line).unwrap();
+        assert_eq!(record.event, HistoryEvent::Run);
+        assert_eq!(record.passed, Some(true));
+        assert_eq!(serde_json::to_string(&record).unwrap(), line);
+    }
+}
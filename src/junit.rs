@@ -0,0 +1,238 @@
+//! JUnit XML reports of `katac run`, so CI systems can show the results of the katas in their
+//! test UI.
+
+use crate::{RunResult, RunStatus, TestOutcome};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// writes a JUnit XML report of the results to the given file
+pub fn write_report(path: &Path, results: &[RunResult]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, report(results))
+}
+
+/// a single `<testcase>` of a suite
+struct Case<'a> {
+    name: &'a str,
+    time: Option<Duration>,
+    /// failure message, None if the case didn't fail
+    failure: Option<String>,
+    skipped: bool,
+}
+
+/// returns the JUnit XML report of the results, with one testsuite per kata
+fn report(results: &[RunResult]) -> String {
+    let suites: Vec<(&RunResult, Vec<Case>)> = results.iter().map(|r| (r, cases(r))).collect();
+    let count = |f: fn(&Case) -> bool| {
+        suites
+            .iter()
+            .map(|(_, cases)| cases.iter().filter(|c| f(c)).count())
+            .sum::<usize>()
+    };
+    let time: Duration = results.iter().map(|r| r.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"katac\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        count(|_| true),
+        count(|c| c.failure.is_some()),
+        count(|c| c.skipped),
+        time.as_secs_f64()
+    );
+    for (result, cases) in &suites {
+        write_suite(&mut xml, result, cases);
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// returns the test cases of a kata: its parsed tests if there are any, otherwise a single case
+/// for the whole kata based on its exit status
+fn cases(result: &RunResult) -> Vec<Case<'_>> {
+    let kata_case = || Case {
+        name: &result.kata_name,
+        time: Some(result.duration),
+        failure: result
+            .status
+            .is_failure()
+            .then(|| result.status.to_string()),
+        skipped: result.status == RunStatus::Skipped,
+    };
+
+    let tests = match &result.tests {
+        Some(tests) => tests,
+        None => return vec![kata_case()],
+    };
+
+    let mut cases: Vec<Case> = tests
+        .iter()
+        .map(|test| Case {
+            name: &test.name,
+            time: test.duration,
+            failure: (test.outcome == TestOutcome::Failed).then(|| "failed".to_string()),
+            skipped: test.outcome == TestOutcome::Skipped,
+        })
+        .collect();
+
+    // a kata can fail without a failing test, e.g. when it timed out or didn't compile
+    if result.status.is_failure() && cases.iter().all(|c| c.failure.is_none()) {
+        cases.push(kata_case());
+    }
+    cases
+}
+
+fn write_suite(xml: &mut String, result: &RunResult, cases: &[Case]) {
+    let name = escape(&result.kata_name);
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        name,
+        cases.len(),
+        cases.iter().filter(|c| c.failure.is_some()).count(),
+        cases.iter().filter(|c| c.skipped).count(),
+        result.duration.as_secs_f64()
+    );
+
+    for case in cases {
+        let _ = write!(
+            xml,
+            "    <testcase name=\"{}\" classname=\"{}\"",
+            escape(case.name),
+            name
+        );
+        if let Some(time) = case.time {
+            let _ = write!(xml, " time=\"{:.3}\"", time.as_secs_f64());
+        }
+        match (&case.failure, case.skipped) {
+            (Some(message), _) => {
+                let _ = writeln!(xml, ">");
+                let _ = writeln!(xml, "      <failure message=\"{}\"/>", escape(message));
+                let _ = writeln!(xml, "    </testcase>");
+            }
+            (None, true) => {
+                let _ = writeln!(xml, ">");
+                let _ = writeln!(xml, "      <skipped/>");
+                let _ = writeln!(xml, "    </testcase>");
+            }
+            (None, false) => {
+                let _ = writeln!(xml, "/>");
+            }
+        }
+    }
+
+    let output = format!("{}{}", result.stdout, result.stderr);
+    if !output.is_empty() {
+        let _ = writeln!(xml, "    <system-out>{}</system-out>", escape(&output));
+    }
+    xml.push_str("  </testsuite>\n");
+}
+
+/// escapes text for XML, dropping terminal color codes and the control characters XML doesn't allow
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // skip escape sequences like `\x1b[31m` until their final byte
+            '\x1b' if chars.peek() == Some(&'[') => {
+                for c in chars.by_ref().skip(1) {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestCase;
+    use std::path::PathBuf;
+
+    fn result(kata_name: &str, status: RunStatus, tests: Option<Vec<TestCase>>) -> RunResult {
+        RunResult {
+            kata_name: kata_name.to_string(),
+            day: 1,
+            day_name: "day1".to_string(),
+            path: PathBuf::from("days/day1").join(kata_name),
+            command: Some("make run -s".to_string()),
+            status,
+            duration: Duration::from_millis(1500),
+            stdout: "out <1>\n".to_string(),
+            stderr: String::new(),
+            tests,
+        }
+    }
+
+    fn test(name: &str, outcome: TestOutcome) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            outcome,
+            duration: None,
+        }
+    }
+
+    #[test]
+    fn one_case_per_kata_without_parsed_tests() {
+        let xml = report(&[
+            result("LRU", RunStatus::Passed, None),
+            result("Trie", RunStatus::Failed(Some(2)), None),
+        ]);
+        assert!(xml.contains(
+            "<testsuites name=\"katac\" tests=\"2\" failures=\"1\" skipped=\"0\" time=\"3.000\">"
+        ));
+        assert!(xml.contains("<testcase name=\"LRU\" classname=\"LRU\" time=\"1.500\"/>"));
+        assert!(xml.contains("<failure message=\"failed (2)\"/>"));
+        assert!(xml.contains("<system-out>out &lt;1&gt;\n</system-out>"));
+    }
+
+    #[test]
+    fn parsed_tests_become_cases() {
+        let tests = vec![
+            test("TestGet", TestOutcome::Passed),
+            test("TestEvict", TestOutcome::Failed),
+            test("TestBig", TestOutcome::Skipped),
+        ];
+        let xml = report(&[result("LRU", RunStatus::Failed(Some(1)), Some(tests))]);
+        assert!(xml.contains(
+            "<testsuite name=\"LRU\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"1.500\">"
+        ));
+        assert!(xml.contains("<testcase name=\"TestGet\" classname=\"LRU\"/>"));
+        assert!(xml.contains(
+            "<testcase name=\"TestEvict\" classname=\"LRU\">\n      <failure message=\"failed\"/>"
+        ));
+        assert!(xml.contains("<testcase name=\"TestBig\" classname=\"LRU\">\n      <skipped/>"));
+    }
+
+    #[test]
+    fn failed_kata_without_failed_tests_adds_a_case() {
+        let tests = vec![test("TestGet", TestOutcome::Passed)];
+        let xml = report(&[result("LRU", RunStatus::TimedOut, Some(tests))]);
+        assert!(xml.contains("tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<failure message=\"timed out\"/>"));
+    }
+
+    #[test]
+    fn escape_drops_color_codes() {
+        assert_eq!(
+            escape("\x1b[31m'a' & \"b\"\x1b[0m\n"),
+            "&apos;a&apos; &amp; &quot;b&quot;\n"
+        );
+    }
+}
@@ -1,19 +1,70 @@
-use clap::{Parser, Subcommand};
+use chrono::{Local, NaiveDate};
+use clap::{Parser, Subcommand, ValueEnum};
 use fs_extra::dir::CopyOptions;
 use include_dir::{include_dir, Dir};
-use inquire::{MultiSelect, Select};
-use log::info;
-use rand::{self, seq::SliceRandom, thread_rng};
-use serde::Deserialize;
-use std::collections::HashSet;
+use log::{info, warn};
+use rand::{self, rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
+
+mod command_line;
+mod config;
+mod days;
+mod diff;
+mod error;
+mod history;
+mod junit;
+mod manifest;
+mod parsers;
+mod pools;
+#[cfg(unix)]
+mod process_group;
+mod runners;
+mod schedule;
+mod sources;
+mod stats;
+mod streak;
+#[cfg(test)]
+mod test_util;
+mod workspace;
+
+use command_line::{makefile_recipe, CommandLine, CustomCommand};
+pub use config::{user_config_path, Origin, USER_CONFIG_FILE};
+pub use days::{DayScheme, Days};
+pub use diff::ColorChoice;
+pub use error::Error;
+pub use history::{History, HistoryEvent, HistoryRecord, HISTORY_FILE};
+pub use manifest::{Difficulty, KataManifest, MANIFEST_FILE};
+pub use parsers::{ResultParser, TestCase, TestOutcome};
+use pools::{Candidate, Pool, PoolConfig, PoolKata};
+#[cfg(unix)]
+pub use process_group::kill_running_katas;
+pub use runners::{
+    BatchRunner, CargoRunner, EmbeddedRunner, ExplicitRunner, JustRunner, MakeRunner, NpmRunner,
+    Runner, Runners, ShellRunner,
+};
+pub use schedule::{Pick, RandomStrategy};
+pub use sources::{
+    open_source, ArchiveSource, DirSource, EmbeddedSource, GitSource, KataSource, Template,
+    EMBEDDED_SOURCE,
+};
+pub use stats::{format_duration, format_rate, Stats, StatsFilter};
+pub use streak::{Streak, Streaks};
+pub use workspace::{Workspace, WorkspaceOptions};
 
 const KATAS_DIR: &str = "katas";
 const DAYS_DIR: &str = "days";
-const CONFIG_FILE_NAME: &str = "katac.toml";
+/// name of the config file of the project
+pub const CONFIG_FILE_NAME: &str = "katac.toml";
+/// where `katac reset` backs up the attempts
+const TRASH_DIR: &str = ".katac/trash";
 
 // Embed the example-katas directory at compile time
 static EXAMPLE_KATAS: Dir = include_dir!("$CARGO_MANIFEST_DIR/example-katas");
@@ -34,6 +85,15 @@ pub struct Args {
     #[arg(short, long)]
     pub config: Option<String>,
 
+    /// Copy the katas that aren't in the katas folder from this template source (default: the
+    /// source of the config file, if set)
+    #[arg(long)]
+    pub source: Option<String>,
+
+    /// Output format, json and ndjson print machine readable records
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    pub format: OutputFormat,
+
     #[command(subcommand)]
     pub subcommand: Option<Subcommands>,
 
@@ -42,13 +102,26 @@ pub struct Args {
     pub kata_names: Vec<String>,
 }
 
+/// output format of the commands
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// human readable output
+    #[default]
+    Text,
+    /// a single json array with every record, printed at the end
+    Json,
+    /// one json record per line, printed as soon as it is available
+    Ndjson,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
     /// Initialize katas by selecting from example templates (uses embedded katas by default)
     Init {
-        /// Optional path to external examples directory (default: uses embedded katas)
-        #[arg(long)]
-        examples_dir: Option<String>,
+        /// Where to copy the templates from: `embedded`, a folder, a .zip/.tar.gz archive or a
+        /// git repository ending in .git (default: the source of the config file, or embedded)
+        #[arg(long, alias = "examples-dir")]
+        source: Option<String>,
 
         /// Select katas without interactive prompt (for testing/automation)
         #[arg(long, hide = true)]
@@ -60,6 +133,23 @@ pub enum Subcommands {
         /// Katas to copy to new day
         #[arg(required = true, num_args = 1..)]
         kata_names: Vec<String>,
+
+        /// Add the katas to the current day instead of starting a new one
+        #[arg(long, conflicts_with_all = ["day", "new_day"])]
+        today: bool,
+
+        /// Add the katas to this day, e.g. 3, 2026-10-17 or 2026-W42/mon depending on the day
+        /// scheme
+        #[arg(long, conflicts_with = "new_day")]
+        day: Option<String>,
+
+        /// Start a new day, even if the config file adds katas to the current day
+        #[arg(long)]
+        new_day: bool,
+
+        /// Replace the katas that are already in the day
+        #[arg(short, long)]
+        force: bool,
     },
 
     /// Katas you want to run today (requires a makefile with the  'run' target in the kata's root folder)
@@ -71,6 +161,30 @@ pub enum Subcommands {
         /// Run custom command for given kata
         #[arg(short, long)]
         command: Option<String>,
+
+        /// Run the custom command through the shell (sh -c), to use pipes, && or variables
+        #[arg(long, requires = "command")]
+        shell: bool,
+
+        /// Keep watching the katas and re-run them when their files change
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Number of katas to run at the same time
+        #[arg(short, long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: u16,
+
+        /// Seconds a kata may run before it is killed (overrides the config file)
+        #[arg(short, long)]
+        timeout: Option<u64>,
+
+        /// Format of the test output, used to report which tests passed (overrides the config file)
+        #[arg(short, long, value_enum)]
+        parser: Option<ResultParser>,
+
+        /// Write a JUnit XML report of the results to the given file
+        #[arg(long, value_name = "FILE")]
+        junit: Option<PathBuf>,
     },
 
     /// Create a new kata
@@ -85,6 +199,109 @@ pub enum Subcommands {
         /// Katas to run
         #[arg(required = true, num_args = 1..)]
         number_of_katas: u8,
+
+        /// How to pick the katas (overrides the config file)
+        #[arg(short, long, value_enum)]
+        strategy: Option<RandomStrategy>,
+
+        /// Seed of the random picks, the same seed picks the same katas
+        #[arg(long, conflicts_with = "seed_from_date")]
+        seed: Option<u64>,
+
+        /// Use today's date as the seed, so everyone picks the same katas today
+        #[arg(long)]
+        seed_from_date: bool,
+
+        /// Pick from a pool of the config file, [pools.<name>]
+        #[arg(short, long)]
+        pool: Option<String>,
+    },
+
+    /// Show how often and how well you practiced each kata
+    Stats {
+        /// Only show these katas
+        #[arg(long = "kata", value_name = "KATA")]
+        katas: Vec<String>,
+
+        /// Only count practice from this date on (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<NaiveDate>,
+
+        /// Only show katas in this language
+        #[arg(long)]
+        language: Option<String>,
+    },
+
+    /// Show your daily practice streaks and a heatmap of the katas completed each day
+    Streak {
+        /// Number of weeks shown in the heatmap
+        #[arg(short, long, default_value_t = 12, value_parser = clap::value_parser!(u32).range(1..))]
+        weeks: u32,
+    },
+
+    /// Restore a kata of the current day from its template, backing up your attempt first
+    Reset {
+        /// Kata to reset
+        kata_name: String,
+
+        /// Only reset these files, relative to the kata folder
+        #[arg(long, num_args = 1..)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Show how a kata changed between two days, or since its template
+    Diff {
+        /// Kata to compare
+        kata_name: String,
+
+        /// Day to compare from, the kata's template by default
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Day to compare to, the current day by default
+        #[arg(long)]
+        to: Option<String>,
+
+        /// When to color the diff
+        #[arg(long, value_enum, default_value_t)]
+        color: ColorChoice,
+    },
+
+    /// Rename the dayN folders after the date they were practiced on, from their mtimes
+    MigrateDays {
+        /// Day scheme to rename the folders to, day_scheme of the config file by default
+        #[arg(long, value_enum)]
+        to: Option<DayScheme>,
+
+        /// Show the new names without renaming anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// List the katas, the days or the example templates
+    List {
+        /// What to list
+        #[arg(value_enum)]
+        kind: ListKind,
+
+        /// Only list katas with this tag in their kata.toml
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Only list katas in this language
+        #[arg(long)]
+        language: Option<String>,
+
+        /// List the templates of this source instead of the one of the config file: `embedded`,
+        /// a folder, a .zip/.tar.gz archive or a git repository ending in .git
+        #[arg(long, alias = "examples-dir")]
+        source: Option<String>,
+    },
+
+    /// Show the config merged from the user and project config files
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
     },
 
     /// Upgrade katac to the latest version
@@ -95,148 +312,669 @@ pub enum Subcommands {
     },
 }
 
-#[derive(Deserialize, Debug)]
+/// what `katac config` does
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the effective value of every config key
+    Show {
+        /// Also print where each value comes from: a config file, an env var, an argument or
+        /// the default
+        #[arg(long)]
+        origin: bool,
+    },
+}
+
+#[derive(Deserialize, Debug, Default)]
 struct Data {
+    #[serde(default)]
     katas: Katas,
+
+    /// per kata settings, under [kata.<name>]
+    #[serde(default)]
+    kata: HashMap<String, KataConfig>,
+
+    /// named pools of katas for katac random, under [pools.<name>]
+    #[serde(default)]
+    pools: HashMap<String, PoolConfig>,
 }
 
 /// config file structure
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 struct Katas {
-    random: Option<Vec<String>>,
+    random: Option<Vec<PoolKata>>,
     katas_dir: Option<String>,
     days_dir: Option<String>,
+    /// seconds a kata may run before it is killed
+    timeout: Option<u64>,
+    /// maximum virtual memory of a kata in megabytes (Linux only)
+    max_memory_mb: Option<u64>,
+    /// maximum CPU time of a kata in seconds (Linux only)
+    max_cpu_secs: Option<u64>,
+    /// format of the test output of the katas
+    parser: Option<ResultParser>,
+    /// where the practice history is kept
+    history_file: Option<String>,
+    /// how katac random picks katas
+    strategy: Option<RandomStrategy>,
+    /// katac random leaves out the katas done in the last N days
+    exclude_days: Option<u32>,
+    /// add katas to the current day instead of starting a new one
+    start_today: Option<bool>,
+    /// how the day folders are named
+    day_scheme: Option<DayScheme>,
+    /// where kata templates come from when they aren't in the katas folder
+    source: Option<String>,
+    /// language preselected when picking templates with katac init
+    language: Option<String>,
+    /// command that opens the katas copied to a day, e.g. `code` or `nvim -p`
+    editor: Option<String>,
 }
 
-// returns the current day number
-fn curday(days_dir: &str) -> u32 {
-    match fs::read_dir(days_dir) {
-        Err(_) => 0,
-        Ok(dir) => dir
-            .filter_map(|e| e.ok())
-            .filter_map(|e| e.file_name().into_string().ok())
-            .map(|e| e.trim_start_matches("day").to_string())
-            .filter_map(|e| e.parse::<u32>().ok())
-            .max()
-            .unwrap_or(0),
-    }
+/// settings for a single kata, they take precedence over the ones in [katas]
+#[derive(Deserialize, Debug, Default)]
+struct KataConfig {
+    timeout: Option<u64>,
+    max_memory_mb: Option<u64>,
+    max_cpu_secs: Option<u64>,
+    parser: Option<ResultParser>,
+    /// how likely katac random is to pick the kata, 1 by default
+    weight: Option<f64>,
+    /// name of the runner that runs the kata, instead of the first one that can
+    runner: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
 }
 
-/// Generic function to get directory with priority:
-/// CLI arg > env var > config file > default value
-fn get_dir(
-    arg_value: &Option<String>,
-    env_var: &str,
-    config_extractor: fn(&Data) -> Option<String>,
-    config_file: &Option<String>,
-    default: &str,
-) -> String {
-    if let Some(dir) = arg_value {
-        return dir.clone();
+/// adds the records to the history, only warning if it can't be written
+fn record_history(history: &History, records: &[HistoryRecord]) {
+    if let Err(e) = history.append(records) {
+        warn!(
+            "failed to record history in {}: {}",
+            history.path().display(),
+            e
+        );
     }
+}
 
-    if let Ok(env_value) = std::env::var(env_var) {
-        return env_value;
+/// returns the kata.toml of the kata in the given folder, an empty one if it has none or it
+/// can't be read
+fn kata_manifest(kata_path: &Path) -> KataManifest {
+    match KataManifest::read(kata_path) {
+        Ok(manifest) => manifest.unwrap_or_default(),
+        Err(e) => {
+            warn!("ignoring {}", e);
+            KataManifest::default()
+        }
     }
+}
 
-    let config_file_name = config_file
-        .as_ref()
-        .map(|s| s.as_str())
-        .unwrap_or(CONFIG_FILE_NAME);
-
-    // Only read config file if it exists
-    if Path::new(config_file_name).exists() {
-        if let Some(config_value) =
-            config_extractor(&read_config_file(config_file_name.to_string()))
-        {
-            return config_value;
+/// returns the language of the kata in the given folder, the one of its kata.toml or the
+/// extension of most of its source files
+pub(crate) fn kata_language(kata_path: &Path) -> Option<String> {
+    if let Some(language) = kata_manifest(kata_path).language {
+        return Some(language);
+    }
+
+    const LANGUAGES: [(&str, &str); 14] = [
+        ("go", "go"),
+        ("py", "python"),
+        ("js", "javascript"),
+        ("mjs", "javascript"),
+        ("ts", "typescript"),
+        ("rs", "rust"),
+        ("java", "java"),
+        ("kt", "kotlin"),
+        ("c", "c"),
+        ("cpp", "cpp"),
+        ("cs", "csharp"),
+        ("rb", "ruby"),
+        ("lua", "lua"),
+        ("zig", "zig"),
+    ];
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for entry in fs::read_dir(kata_path).ok()?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        if let Some((_, language)) = LANGUAGES.iter().find(|(ext, _)| *ext == extension) {
+            *counts.entry(language).or_default() += 1;
         }
     }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+        .map(|(language, _)| language.to_string())
+}
+
+/// the day katas are copied to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetDay {
+    /// a new day after the current one
+    Next,
+    /// the current day, or the first one if there are no days yet
+    Current,
+    /// a day by its folder name, e.g. `day3` or `2026-10-17`
+    Day(String),
+}
+
+/// options of copy_katas
+#[derive(Debug, Clone, Default)]
+pub struct StartOptions {
+    /// day to copy the katas to, the one of the config file if None
+    pub day: Option<TargetDay>,
+    /// replace the katas that are already in the day
+    pub force: bool,
+}
 
-    default.to_string()
-}
-
-/// priorities are:
-/// --katas-dir arg
-/// KATAS_DIR env var
-/// katas_dir config file property
-/// default value
-fn katas_dir(args: &Args) -> String {
-    get_dir(
-        &args.katas_dir,
-        "KATAS_DIR",
-        |data| data.katas.katas_dir.clone(),
-        &args.config,
-        KATAS_DIR,
-    )
-}
-
-/// priorities are:
-/// --days-dir arg
-/// DAYS_DIR env var
-/// days_dir config file property
-/// default value
-fn days_dir(args: &Args) -> String {
-    get_dir(
-        &args.days_dir,
-        "DAYS_DIR",
-        |data| data.katas.days_dir.clone(),
-        &args.config,
-        DAYS_DIR,
-    )
-}
-
-/// copies katas from the katas_dir to a new day in days_dir
-pub fn copy_katas(args: &Args, kata_names: &Vec<String>) {
+/// copies katas from the katas_dir to a day in days_dir, a new one by default, and records them
+/// in the history as the given event. Returns the katas that were copied, or every kata that
+/// couldn't be copied along with the ones that were
+fn copy_katas(
+    workspace: &Workspace,
+    kata_names: &[String],
+    event: HistoryEvent,
+    options: &StartOptions,
+) -> Result<Vec<CopyRecord>, Error> {
     if kata_names.is_empty() {
-        eprintln!("Error: no katas specified");
-        std::process::exit(1);
+        return Err(Error::InvalidInput("no katas specified".to_string()));
     }
 
-    let dst = nextday_path(&days_dir(args));
+    let days = &workspace.days;
+    let target = match options.day.clone() {
+        Some(target) => target,
+        None => match workspace.config.katas.start_today {
+            Some(true) => TargetDay::Current,
+            _ => TargetDay::Next,
+        },
+    };
+    let day = match target {
+        TargetDay::Next => days.next(),
+        TargetDay::Current => days.current_or_next(),
+        TargetDay::Day(name) => parse_day(days, &name)?,
+    };
+    let katas_dir = &workspace.katas_dir;
+    let history = &workspace.history;
+    let dst = days.path(day);
     let mut errors = Vec::new();
+    let mut copied = Vec::new();
+    let mut history_records = Vec::new();
 
     for kata_name in kata_names {
         // Validate kata name if it's not a path
         if !kata_name.contains('/') && kata_name.contains("..") {
-            eprintln!("Error: kata name '{}' cannot contain '..'", kata_name);
-            errors.push(kata_name.clone());
+            errors.push(Error::InvalidName {
+                name: kata_name.clone(),
+                reason: "cannot contain '..'",
+            });
             continue;
         }
 
-        let src = kata_path(kata_name, &katas_dir(args));
+        let src = kata_path(kata_name, katas_dir);
+        let copied_path = dst.join(basename(&src));
+        if copied_path.exists() && !options.force {
+            errors.push(Error::AlreadyInDay {
+                kata: kata_name.clone(),
+                day: days.name(day),
+            });
+            continue;
+        }
+        // katas that aren't in the katas folder come from the source of the workspace
+        let (src, staging) = match src.exists() || kata_name.contains('/') {
+            true => (src, None),
+            false => match kata_template(workspace, kata_name) {
+                Ok(template) => template,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            },
+        };
         if !src.exists() {
-            eprintln!("Error: Kata '{}' does not exist", kata_name);
-            errors.push(kata_name.clone());
+            errors.push(Error::KataNotFound {
+                kata: kata_name.clone(),
+                location: katas_dir.clone(),
+            });
             continue;
         }
         if !dst.exists() {
-            if let Err(e) = fs::create_dir_all(&dst) {
-                eprintln!("Error: Failed to create days folder: {}", e);
-                std::process::exit(1);
-            }
+            fs::create_dir_all(&dst).map_err(Error::io(format!(
+                "failed to create the day folder {}",
+                dst.display()
+            )))?;
         }
-        match fs_extra::copy_items(&[&src], &dst, &CopyOptions::new()) {
+        remove_path(&copied_path);
+
+        let manifest = kata_manifest(&src);
+        let copy_result = match staging {
+            None => fs_extra::copy_items(&[&src], &dst, &CopyOptions::new()),
+            Some(_) => {
+                let options = CopyOptions {
+                    copy_inside: true,
+                    ..CopyOptions::new()
+                };
+                fs_extra::dir::copy(&src, &copied_path, &options)
+            }
+        };
+        let source = match staging {
+            None => src.display().to_string(),
+            Some(ref staging) => {
+                remove_path(staging);
+                format!("{}/{}", workspace.source.name(), kata_name)
+            }
+        };
+        match copy_result {
             Ok(_) => {
-                println!("Copying {} to {}...", kata_name, basename(&dst));
+                if let Some(solution) = &manifest.solution {
+                    remove_path(&copied_path.join(solution));
+                }
+                history_records.push(HistoryRecord::new(
+                    event,
+                    day,
+                    kata_name,
+                    kata_language(&copied_path),
+                ));
 
                 // Check if this is an embedded kata and ensure Makefile exists
-                let copied_path = dst.join(kata_name);
-                if let Some((language, _)) = is_embedded_kata(kata_name) {
-                    ensure_makefile_exists(&copied_path, &language, kata_name);
-                }
-            }
-            Err(e) => {
-                eprintln!("Error copying '{}': {}", kata_name, e);
-                errors.push(kata_name.clone());
+                let created_makefile = match is_embedded_kata(kata_name) {
+                    Some((language, _)) => {
+                        ensure_makefile_exists(&copied_path, &language, kata_name)
+                    }
+                    None => false,
+                };
+                copied.push(CopyRecord {
+                    kata: kata_name.clone(),
+                    day,
+                    day_name: days.name(day),
+                    source,
+                    destination: copied_path.display().to_string(),
+                    from_source: staging.is_some(),
+                    summary: manifest.summary(),
+                    created_makefile,
+                });
             }
+            Err(e) => errors.push(Error::io(format!("failed to copy '{}'", kata_name))(
+                fs_extra_error(e),
+            )),
         }
     }
 
+    record_history(history, &history_records);
+
     if !errors.is_empty() {
-        eprintln!("\nFailed to copy {} kata(s)", errors.len());
-        std::process::exit(1);
+        return Err(Error::CopyFailed { copied, errors });
+    }
+    Ok(copied)
+}
+
+/// opens the katas at the given paths with the editor of the config file, does nothing without
+/// one
+fn open_in_editor(workspace: &Workspace, paths: &[PathBuf]) -> Result<(), Error> {
+    let editor = match &workspace.config.katas.editor {
+        Some(editor) => editor,
+        None => return Ok(()),
+    };
+    let line = CommandLine::parse(editor).map_err(|e| {
+        Error::InvalidInput(format!(
+            "invalid editor '{}' in the config file: {}",
+            editor, e
+        ))
+    })?;
+    line.to_command()
+        .args(paths)
+        .status()
+        .map_err(Error::io(format!(
+            "failed to open the katas with '{}'",
+            editor
+        )))?;
+    Ok(())
+}
+
+/// converts an error of fs_extra into an io error
+fn fs_extra_error(e: fs_extra::error::Error) -> std::io::Error {
+    std::io::Error::other(e.to_string())
+}
+
+/// parses a day given on the command line, fails if it doesn't fit the day scheme
+fn parse_day(days: &Days, name: &str) -> Result<u32, Error> {
+    days.parse(name).ok_or_else(|| Error::InvalidDay {
+        day: name.to_string(),
+        scheme: days.scheme(),
+        example: days.name(days.next()),
+    })
+}
+
+/// returns the template of a kata: its folder in the katas folder, or the template of the
+/// source of the workspace copied to a temporary folder, returned second so it can be removed
+/// when done. The source is only used when it was configured
+fn kata_template(
+    workspace: &Workspace,
+    kata_name: &str,
+) -> Result<(PathBuf, Option<PathBuf>), Error> {
+    validate_kata_name(kata_name)?;
+    let katas_dir = &workspace.katas_dir;
+    let template = kata_path(kata_name, katas_dir);
+    if template.is_dir() {
+        return Ok((template, None));
+    }
+    if !workspace.source_fallback {
+        return Err(Error::KataNotFound {
+            kata: kata_name.to_string(),
+            location: katas_dir.clone(),
+        });
+    }
+    let source = workspace.source.as_ref();
+    let Some(found) = source.find(kata_name)? else {
+        return Err(Error::KataNotFound {
+            kata: kata_name.to_string(),
+            location: format!("{} or in the {} katas", katas_dir, source.name()),
+        });
+    };
+    let dir = sources::temp_source_dir(&format!("template_{}", kata_name));
+    if let Err(e) = source.fetch(&found, &dir) {
+        remove_path(&dir);
+        return Err(e);
+    }
+    ensure_makefile_exists(&dir, &found.language, kata_name);
+    Ok((dir.clone(), Some(dir)))
+}
+
+/// fails if the kata name isn't the name of a single folder, so it can't point outside the
+/// folder it is joined to
+fn validate_kata_name(kata_name: &str) -> Result<(), Error> {
+    let invalid = |reason| {
+        Err(Error::InvalidName {
+            name: kata_name.to_string(),
+            reason,
+        })
+    };
+    if kata_name.is_empty() {
+        return invalid("cannot be empty");
+    }
+    if kata_name.contains("..") {
+        return invalid("cannot contain '..'");
+    }
+    if kata_name.starts_with('.') {
+        return invalid("cannot start with '.'");
+    }
+    if kata_name.contains(['/', '\\']) {
+        return invalid("cannot contain a path separator");
     }
+    Ok(())
+}
+
+/// returns the folder of a kata in a day, failing unless it is a folder right inside the day
+/// folder once symlinks are resolved. The kata name must be valid, see validate_kata_name
+fn day_kata_dir(days: &Days, day: u32, kata_name: &str) -> Result<PathBuf, Error> {
+    let not_found = || Error::KataNotFound {
+        kata: kata_name.to_string(),
+        location: days.name(day),
+    };
+    let day_dir = days.path(day);
+    let kata_dir = day_dir.join(kata_name);
+    if !kata_dir.is_dir() {
+        return Err(not_found());
+    }
+    let (Ok(day_dir), Ok(canonical)) = (day_dir.canonicalize(), kata_dir.canonicalize()) else {
+        return Err(not_found());
+    };
+    if canonical.parent() != Some(day_dir.as_path()) {
+        return Err(Error::InvalidInput(format!(
+            "{} is not a kata folder of {}",
+            kata_dir.display(),
+            days.name(day)
+        )));
+    }
+    Ok(kata_dir)
+}
+
+/// removes a file or a folder, if it exists
+fn remove_path(path: &Path) {
+    let removed = match path.is_dir() {
+        true => fs::remove_dir_all(path),
+        false => fs::remove_file(path),
+    };
+    if let Err(e) = removed.or_else(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => Ok(()),
+        _ => Err(e),
+    }) {
+        warn!("failed to remove {}: {}", path.display(), e);
+    }
+}
+
+/// a kata restored by `katac reset`
+#[derive(Debug, Clone)]
+pub struct ResetResult {
+    pub kata: String,
+    /// name of the day folder the kata is in
+    pub day_name: String,
+    /// where the attempt was backed up to
+    pub backup: PathBuf,
+    /// the files that were restored, empty if the whole kata was
+    pub files: Vec<PathBuf>,
+}
+
+/// restores a kata of the current day from its template in the katas folder, or the embedded
+/// one. The attempt is first backed up to the trash folder of the workspace. Only the given
+/// files are restored, or the `reset` files of the kata.toml, or the whole kata
+fn reset_kata(
+    workspace: &Workspace,
+    kata_name: &str,
+    files: &[PathBuf],
+) -> Result<ResetResult, Error> {
+    let days = &workspace.days;
+    let day = days.current();
+    validate_kata_name(kata_name)?;
+    if day == 0 {
+        return Err(Error::KataNotFound {
+            kata: kata_name.to_string(),
+            location: "the current day".to_string(),
+        });
+    }
+    let kata_dir = day_kata_dir(days, day, kata_name)?;
+    if let Some(file) = files.iter().find(|f| !manifest::is_inside_kata(f)) {
+        return Err(Error::InvalidInput(format!(
+            "'{}' is not a path inside the kata folder",
+            file.display()
+        )));
+    }
+
+    let (template, staging) = kata_template(workspace, kata_name)?;
+
+    let manifest = kata_manifest(&template);
+    let files = match files {
+        [] => manifest.reset.clone(),
+        files => files.to_vec(),
+    };
+    let result = restore_kata(&days.name(day), &kata_dir, &template, &files, &manifest);
+    if let Some(staging) = staging {
+        remove_path(&staging);
+    }
+    Ok(ResetResult {
+        kata: kata_name.to_string(),
+        day_name: days.name(day),
+        backup: result?,
+        files,
+    })
+}
+
+/// backs up the kata folder to the trash and restores the files, or all of it, from the
+/// template. Returns where the backup is
+fn restore_kata(
+    day_name: &str,
+    kata_dir: &Path,
+    template: &Path,
+    files: &[PathBuf],
+    manifest: &KataManifest,
+) -> Result<PathBuf, Error> {
+    if let Some(file) = files.iter().find(|f| !template.join(f).exists()) {
+        return Err(Error::InvalidInput(format!(
+            "'{}' is not in the template of {}",
+            file.display(),
+            basename(kata_dir)
+        )));
+    }
+
+    let trash = Path::new(TRASH_DIR);
+    let name = format!(
+        "{}_{}_{}",
+        day_name.replace('/', "-"),
+        basename(kata_dir),
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+    // resets within the same second don't overwrite each other's backup
+    let backup = (1..)
+        .map(|n| match n {
+            1 => trash.join(&name),
+            n => trash.join(format!("{}_{}", name, n)),
+        })
+        .find(|path| !path.exists())
+        .unwrap_or_default();
+    let copy_options = CopyOptions {
+        copy_inside: true,
+        ..CopyOptions::new()
+    };
+    let backup_error = Error::io(format!(
+        "failed to back up {} to {}",
+        kata_dir.display(),
+        backup.display()
+    ));
+    fs::create_dir_all(trash)
+        .and_then(|_| fs_extra::dir::copy(kata_dir, &backup, &copy_options).map_err(fs_extra_error))
+        .map_err(backup_error)?;
+
+    let restore_error = |file: &Path| {
+        Error::io(format!(
+            "failed to restore {} from {}",
+            kata_dir.join(file).display(),
+            template.join(file).display()
+        ))
+    };
+    if files.is_empty() {
+        fs::remove_dir_all(kata_dir)
+            .and_then(|_| {
+                fs_extra::dir::copy(template, kata_dir, &copy_options).map_err(fs_extra_error)
+            })
+            .map_err(restore_error(Path::new("")))?;
+        if let Some(solution) = &manifest.solution {
+            remove_path(&kata_dir.join(solution));
+        }
+        return Ok(backup);
+    }
+
+    for file in files {
+        let (src, dst) = (template.join(file), kata_dir.join(file));
+        remove_path(&dst);
+        let copied = match src.is_dir() {
+            true => fs_extra::dir::copy(&src, &dst, &copy_options)
+                .map(|_| ())
+                .map_err(fs_extra_error),
+            false => dst
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::copy(&src, &dst).map(|_| ())),
+        };
+        copied.map_err(restore_error(file))?;
+    }
+    Ok(backup)
+}
+
+/// record of a kata copied to a day, printed with --format json or ndjson
+#[derive(Serialize, Debug, Clone)]
+pub struct CopyRecord {
+    pub kata: String,
+    pub day: u32,
+    /// name of the day folder, e.g. `day3` or `2026-10-17`
+    pub day_name: String,
+    /// folder the kata was copied from, or `<source>/<kata>` for a template of the source
+    pub source: String,
+    pub destination: String,
+    /// whether the kata was copied from the template source instead of the katas folder
+    #[serde(skip)]
+    pub from_source: bool,
+    /// the line describing the kata, from its kata.toml
+    #[serde(skip)]
+    pub summary: Option<String>,
+    /// whether the Makefile of an embedded kata was added to the copy
+    #[serde(skip)]
+    pub created_makefile: bool,
+}
+
+/// how a kata changed between two days, or since its template
+#[derive(Debug, Clone)]
+pub struct KataDiff {
+    pub kata: String,
+    /// unified diff of the files of the kata, empty if nothing changed
+    pub diff: String,
+}
+
+impl KataDiff {
+    /// returns the diff with ANSI colors
+    pub fn colorized(&self) -> String {
+        diff::colorize(&self.diff)
+    }
+}
+
+/// returns the unified diff of a kata between two days, the template and the current day by
+/// default. Build artifacts are skipped
+fn diff_kata(
+    workspace: &Workspace,
+    kata_name: &str,
+    from: &Option<String>,
+    to: &Option<String>,
+) -> Result<KataDiff, Error> {
+    let days = &workspace.days;
+    let not_found = |day| Error::KataNotFound {
+        kata: kata_name.to_string(),
+        location: days.name(day),
+    };
+    validate_kata_name(kata_name)?;
+    let to = match to {
+        Some(name) => parse_day(days, name)?,
+        None => days.current(),
+    };
+    if to == 0 {
+        return Err(not_found(to));
+    }
+    let to_dir = day_kata_dir(days, to, kata_name)?;
+
+    let (from_dir, from_label, solution, staging) = match from {
+        Some(name) => {
+            let from = parse_day(days, name)?;
+            let from_dir = day_kata_dir(days, from, kata_name)?;
+            let label = format!("{}/{}", days.name(from), kata_name);
+            (from_dir, label, None, None)
+        }
+        None => {
+            let (template, staging) = kata_template(workspace, kata_name)?;
+            // the solution is never copied to the days, so it isn't a change
+            let solution = kata_manifest(&template).solution;
+            let label = format!("template/{}", kata_name);
+            (template, label, solution, staging)
+        }
+    };
+
+    let diff = diff::diff_dirs(
+        &diff::DiffSide {
+            dir: &from_dir,
+            label: &from_label,
+            excluded: solution.as_deref(),
+        },
+        &diff::DiffSide {
+            dir: &to_dir,
+            label: &format!("{}/{}", days.name(to), kata_name),
+            excluded: None,
+        },
+    );
+    if let Some(staging) = staging {
+        remove_path(&staging);
+    }
+    Ok(KataDiff {
+        kata: kata_name.to_string(),
+        diff,
+    })
 }
 
 /// returns the basename of a path
@@ -255,277 +993,1655 @@ fn dirname(path: &Path) -> String {
         .to_string()
 }
 
-/// runs the katas in the current day
-pub fn run_katas(args: &Args, kata_names: &Option<Vec<String>>, command: &Option<String>) {
-    let days_dir = days_dir(args);
-    let curday_path = curday_path(&days_dir);
+/// outcome of running a single kata
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunStatus {
+    /// the run command exited successfully
+    Passed,
+    /// the run command failed, with its exit code if it exited normally
+    Failed(Option<i32>),
+    /// the kata ran past its timeout and was killed
+    TimedOut,
+    /// nothing to run (no Makefile, run.sh or run.bat)
+    Skipped,
+}
+
+impl RunStatus {
+    /// returns true if the kata failed or timed out
+    pub fn is_failure(&self) -> bool {
+        matches!(self, RunStatus::Failed(_) | RunStatus::TimedOut)
+    }
+
+    /// returns the name of the status used in machine readable output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Passed => "passed",
+            RunStatus::Failed(_) => "failed",
+            RunStatus::TimedOut => "timed_out",
+            RunStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl std::fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunStatus::Passed => write!(f, "passed"),
+            RunStatus::Failed(Some(code)) => write!(f, "failed ({})", code),
+            RunStatus::Failed(None) => write!(f, "failed"),
+            RunStatus::TimedOut => write!(f, "timed out"),
+            RunStatus::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+/// result of running a single kata
+#[derive(Debug, Clone)]
+pub struct RunResult {
+    pub kata_name: String,
+    /// number of the day the kata is in
+    pub day: u32,
+    /// name of the day folder, e.g. `day3` or `2026-10-17`
+    pub day_name: String,
+    /// folder of the kata in the current day
+    pub path: PathBuf,
+    /// command line that was run, None if the kata was skipped
+    pub command: Option<String>,
+    pub status: RunStatus,
+    pub duration: Duration,
+    /// captured output, empty when it went straight to the terminal
+    pub stdout: String,
+    pub stderr: String,
+    /// results of the single tests, if they were found in the output
+    pub tests: Option<Vec<TestCase>>,
+}
+
+impl RunResult {
+    /// returns the number of tests that passed and the number of tests found
+    pub fn tests_passed(&self) -> Option<(usize, usize)> {
+        self.tests.as_ref().map(|tests| {
+            let passed = tests
+                .iter()
+                .filter(|t| t.outcome == TestOutcome::Passed)
+                .count();
+            (passed, tests.len())
+        })
+    }
+
+    /// returns the names of the tests that failed
+    pub fn failed_tests(&self) -> Vec<&str> {
+        self.tests
+            .iter()
+            .flatten()
+            .filter(|t| t.outcome == TestOutcome::Failed)
+            .map(|t| t.name.as_str())
+            .collect()
+    }
+}
+
+/// maximum number of bytes of stdout/stderr kept in a json record
+const RECORD_OUTPUT_LIMIT: usize = 64 * 1024;
+
+/// record of a kata run, printed with --format json or ndjson
+#[derive(Serialize, Debug)]
+pub struct RunRecord<'a> {
+    pub kata: &'a str,
+    pub day: u32,
+    /// name of the day folder, e.g. `day3` or `2026-10-17`
+    pub day_name: &'a str,
+    pub path: String,
+    pub command: Option<&'a str>,
+    pub status: &'static str,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub tests: Option<TestsRecord<'a>>,
+}
+
+/// test results of a kata run in a json record
+#[derive(Serialize, Debug)]
+pub struct TestsRecord<'a> {
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub failures: Vec<&'a str>,
+}
+
+impl<'a> TestsRecord<'a> {
+    fn new(result: &'a RunResult) -> Option<Self> {
+        let tests = result.tests.as_ref()?;
+        let count = |outcome| tests.iter().filter(|t| t.outcome == outcome).count();
+        Some(TestsRecord {
+            total: tests.len(),
+            passed: count(TestOutcome::Passed),
+            failed: count(TestOutcome::Failed),
+            skipped: count(TestOutcome::Skipped),
+            failures: result.failed_tests(),
+        })
+    }
+}
+
+impl<'a> RunRecord<'a> {
+    pub fn new(result: &'a RunResult) -> Self {
+        RunRecord {
+            kata: &result.kata_name,
+            day: result.day,
+            day_name: &result.day_name,
+            path: result.path.display().to_string(),
+            command: result.command.as_deref(),
+            status: result.status.as_str(),
+            exit_code: match result.status {
+                RunStatus::Passed => Some(0),
+                RunStatus::Failed(code) => code,
+                _ => None,
+            },
+            duration_ms: result.duration.as_millis() as u64,
+            timed_out: result.status == RunStatus::TimedOut,
+            stdout: truncate_output(&result.stdout),
+            stderr: truncate_output(&result.stderr),
+            tests: TestsRecord::new(result),
+        }
+    }
+}
+
+/// keeps the end of a kata's output, where failures usually are, if it is too long for a record
+fn truncate_output(output: &str) -> String {
+    if output.len() <= RECORD_OUTPUT_LIMIT {
+        return output.to_string();
+    }
+    let mut start = output.len() - RECORD_OUTPUT_LIMIT;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[... {} bytes truncated]\n{}", start, &output[start..])
+}
+
+/// options of the run subcommand
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    /// custom command to run instead of the kata's Makefile or run file
+    pub command: Option<String>,
+    /// run the custom command through the shell instead of parsing it
+    pub shell: bool,
+    /// keep running and re-run katas when their files change
+    pub watch: bool,
+    /// number of katas to run at the same time
+    pub jobs: usize,
+    /// seconds a kata may run before it is killed, overrides the config file
+    pub timeout: Option<u64>,
+    /// format of the test output, overrides the config file
+    pub parser: Option<ResultParser>,
+    /// file to write a JUnit XML report of the results to
+    pub junit: Option<PathBuf>,
+    /// capture the output of the katas instead of letting them write to the terminal. It is
+    /// then reported as `RunEvent::Output`, with the headers, once each kata finishes
+    pub capture_output: bool,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions {
+            command: None,
+            shell: false,
+            watch: false,
+            jobs: 1,
+            timeout: None,
+            parser: None,
+            junit: None,
+            capture_output: false,
+        }
+    }
+}
+
+/// what happens while the katas run, reported in order from the thread that called
+/// `Workspace::run`
+#[derive(Debug)]
+pub enum RunEvent<'a> {
+    /// the `> Running` headers, notes about how the katas are run, how many of their tests
+    /// passed and, with `capture_output` or several jobs, the output of the katas
+    Output(&'a [u8]),
+    /// a kata finished
+    Finished(&'a RunResult),
+    /// every kata of a batch finished: the katas given to `Workspace::run`, or the ones re-run
+    /// in watch mode. The runs were recorded in the history
+    BatchFinished(&'a [RunResult]),
+    /// watch mode waits for the files of the katas to change
+    Watching { katas: usize },
+    /// the files of these katas changed, they are about to be re-run
+    Changed(&'a [String]),
+}
+
+/// writes the output of a kata as `RunEvent::Output`
+struct EventWriter<'a>(&'a mut dyn FnMut(RunEvent));
+
+impl Write for EventWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        (self.0)(RunEvent::Output(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// limits applied to a single kata while it runs
+#[derive(Debug, Clone, Default, PartialEq)]
+struct KataLimits {
+    timeout: Option<Duration>,
+    max_memory_mb: Option<u64>,
+    max_cpu_secs: Option<u64>,
+}
+
+/// everything needed to run the katas of a day
+struct RunContext<'a> {
+    workspace: &'a Workspace,
+    /// number of the current day
+    day: u32,
+    options: &'a RunOptions,
+    /// the parsed --command, if any
+    command: Option<CustomCommand>,
+}
+
+impl RunContext<'_> {
+    /// resolves the limits of a kata with priority:
+    /// --timeout arg > [kata.<name>] config > [katas] config
+    fn limits(&self, kata_name: &str) -> KataLimits {
+        let config = &self.workspace.config;
+        let global = &config.katas;
+        let kata = config.kata.get(kata_name);
+        let timeout = self
+            .options
+            .timeout
+            .or_else(|| kata.and_then(|k| k.timeout))
+            .or(global.timeout);
+
+        KataLimits {
+            timeout: timeout.map(Duration::from_secs),
+            max_memory_mb: kata.and_then(|k| k.max_memory_mb).or(global.max_memory_mb),
+            max_cpu_secs: kata.and_then(|k| k.max_cpu_secs).or(global.max_cpu_secs),
+        }
+    }
+
+    /// builds the command that runs the kata with its runner of the config file, or the first
+    /// runner that can run it. Returns None if there is nothing to run
+    fn runner_command(
+        &self,
+        kata_name: &str,
+        kata_path: &Path,
+        out: &mut dyn Write,
+    ) -> Option<Command> {
+        let runners = &self.workspace.runners;
+        let configured = self
+            .workspace
+            .config
+            .kata
+            .get(kata_name)
+            .and_then(|k| k.runner.as_deref());
+        let mut command = match configured.and_then(|name| runners.get(name)) {
+            Some(runner) => {
+                let command = runner.command(kata_path, out);
+                if command.is_none() {
+                    let _ = writeln!(
+                        out,
+                        "The {} runner has nothing to run in {}",
+                        runner.name(),
+                        kata_path.display()
+                    );
+                }
+                command?
+            }
+            None => match runners.detect(kata_path, out) {
+                Some(command) => command,
+                None => {
+                    let _ = writeln!(
+                        out,
+                        "Nothing to run in {}, add a Makefile or a run.sh",
+                        kata_path.display()
+                    );
+                    return None;
+                }
+            },
+        };
+        command.current_dir(kata_path);
+        Some(command)
+    }
+
+    /// resolves the parser of a kata's output with priority:
+    /// --parser arg > [kata.<name>] config > [katas] config, off by default
+    fn parser(&self, kata_name: &str) -> ResultParser {
+        let config = &self.workspace.config;
+        self.options
+            .parser
+            .or_else(|| config.kata.get(kata_name).and_then(|k| k.parser))
+            .or(config.katas.parser)
+            .unwrap_or(ResultParser::Off)
+    }
+}
+
+/// where the output of a running kata goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputMode {
+    /// straight to the terminal
+    Inherit,
+    /// captured, to be printed later
+    Buffer,
+    /// to the terminal as it is written, and captured
+    Tee,
+}
+
+/// runs the katas in the current day, reporting what happens to `on_event`, and returns their
+/// results, or the katas that failed. Up to `jobs` katas run at the same time. With `watch`,
+/// keeps running and re-runs katas whenever their files change.
+fn run_katas(
+    workspace: &Workspace,
+    kata_names: &Option<Vec<String>>,
+    options: &RunOptions,
+    on_event: &mut dyn FnMut(RunEvent),
+) -> Result<Vec<RunResult>, Error> {
+    let command = options
+        .command
+        .as_ref()
+        .map(|cmd_str| CustomCommand::parse(cmd_str, options.shell))
+        .transpose()
+        .map_err(Error::InvalidInput)?;
+
+    let runners = &workspace.runners;
+    for (kata_name, kata) in &workspace.config.kata {
+        if let Some(runner) = kata.runner.as_deref().filter(|r| runners.get(r).is_none()) {
+            return Err(Error::InvalidInput(format!(
+                "unknown runner '{}' for {} in the config file, expected one of: {}",
+                runner,
+                kata_name,
+                runners.names().join(", ")
+            )));
+        }
+    }
+
+    let ctx = RunContext {
+        workspace,
+        day: workspace.days.current(),
+        options,
+        command,
+    };
+    let curday_path = workspace.days.path(ctx.day);
 
     let kata_names = match kata_names {
         Some(kata_names) => kata_names.clone(),
-        None => curday_katas(curday_path),
+        None => curday_katas(curday_path)?,
     };
 
-    for (i, kata_name) in kata_names.iter().enumerate() {
-        let curday_kata_path = curday_kata_path(&days_dir, kata_name);
-        let run_str = format!("\n> Running {} [{}/{}]", kata_name, i + 1, kata_names.len());
-        println!("{}", run_str);
-        let width = run_str.chars().count();
-        println!("{}", "-".repeat(width));
-
-        if let Some(ref cmd_str) = command {
-            let mut command_parts = cmd_str.split_whitespace();
-            match command_parts.next() {
-                Some(cmd) => {
-                    let mut child = Command::new(cmd)
-                        .args(command_parts)
-                        .current_dir(curday_kata_path)
-                        .spawn()
-                        .expect("failed to run the kata");
-                    child.wait().expect("failed to wait on child");
+    let results = run_kata_batch(&ctx, &kata_names, on_event);
+    report_run_results(&ctx, &results, on_event)?;
+
+    if options.watch {
+        watch_katas(&ctx, &kata_names, on_event);
+    }
+
+    let failed: Vec<String> = results
+        .iter()
+        .filter(|r| r.status.is_failure())
+        .map(|r| r.kata_name.clone())
+        .collect();
+    if !failed.is_empty() {
+        return Err(Error::RunFailed { katas: failed });
+    }
+    Ok(results)
+}
+
+/// runs the given katas of the current day, up to `jobs` at a time, and returns their results
+/// in the same order. When running in parallel, each kata's output is buffered and reported
+/// under its header once it finishes, so outputs are never interleaved.
+fn run_kata_batch(
+    ctx: &RunContext,
+    kata_names: &[String],
+    on_event: &mut dyn FnMut(RunEvent),
+) -> Vec<RunResult> {
+    let total = kata_names.len();
+    let jobs = ctx.options.jobs;
+    if !ctx.options.capture_output && (jobs <= 1 || total <= 1) {
+        let mut results = Vec::with_capacity(total);
+        for (i, kata_name) in kata_names.iter().enumerate() {
+            // the output is only needed when it has to be parsed or reported
+            let mode = match ctx.parser(kata_name) {
+                ResultParser::Off if ctx.options.junit.is_none() => OutputMode::Inherit,
+                _ => OutputMode::Tee,
+            };
+            let result = run_kata(ctx, kata_name, i, total, &mut EventWriter(on_event), mode);
+            on_event(RunEvent::Finished(&result));
+            results.push(result);
+        }
+        return results;
+    }
+
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(total) {
+            let tx = tx.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= total {
+                    break;
                 }
-                None => {
-                    println!("Error: empty command provided");
+                let mut output = Vec::new();
+                let result = run_kata(
+                    ctx,
+                    &kata_names[i],
+                    i,
+                    total,
+                    &mut output,
+                    OutputMode::Buffer,
+                );
+                if tx.send((i, result, output)).is_err() {
+                    break;
                 }
+            });
+        }
+        drop(tx);
+
+        // report finished katas in order, holding back the ones that finished early
+        let mut finished = HashMap::new();
+        let mut results = Vec::with_capacity(total);
+        for (i, result, output) in rx {
+            finished.insert(i, (result, output));
+            while let Some((result, output)) = finished.remove(&results.len()) {
+                on_event(RunEvent::Output(&output));
+                on_event(RunEvent::Finished(&result));
+                results.push(result);
             }
-            continue;
         }
+        results
+    })
+}
 
-        match run(curday_kata_path) {
-            Some(mut child) => child.wait().expect("failed to wait on child"),
-            None => continue,
-        };
+/// records the runs of a batch of katas in the history, writes the JUnit report if one was asked
+/// for and reports the batch as finished, also when the report couldn't be written
+fn report_run_results(
+    ctx: &RunContext,
+    results: &[RunResult],
+    on_event: &mut dyn FnMut(RunEvent),
+) -> Result<(), Error> {
+    let history_records: Vec<HistoryRecord> = results
+        .iter()
+        .filter(|r| r.status != RunStatus::Skipped)
+        .map(|r| {
+            HistoryRecord::run(
+                ctx.day,
+                &r.kata_name,
+                kata_language(&r.path),
+                r.status == RunStatus::Passed,
+                r.duration,
+            )
+        })
+        .collect();
+    record_history(&ctx.workspace.history, &history_records);
+
+    let report = match &ctx.options.junit {
+        Some(path) => junit::write_report(path, results).map_err(Error::io(format!(
+            "failed to write the JUnit report to {}",
+            path.display()
+        ))),
+        None => Ok(()),
+    };
+
+    on_event(RunEvent::BatchFinished(results));
+    report
+}
+
+/// runs a single kata of the current day, writing a `> Running` header to `out` before it.
+/// With `OutputMode::Buffer`, the kata's stdout and stderr are also written to `out` instead of
+/// the terminal. When the kata's tests are found in its output, a line with how many passed is
+/// written after it.
+fn run_kata(
+    ctx: &RunContext,
+    kata_name: &str,
+    index: usize,
+    total: usize,
+    out: &mut dyn Write,
+    mode: OutputMode,
+) -> RunResult {
+    let curday_kata_path = ctx.workspace.days.path(ctx.day).join(kata_name);
+    let run_str = format!("\n> Running {} [{}/{}]", kata_name, index + 1, total);
+    let _ = writeln!(out, "{}", run_str);
+    let width = run_str.chars().count();
+    let _ = writeln!(out, "{}", "-".repeat(width));
+
+    let start = Instant::now();
+    let cmd = match ctx.command {
+        Some(ref command) => {
+            let mut cmd = command.to_command();
+            cmd.current_dir(&curday_kata_path);
+            Some(cmd)
+        }
+        None => ctx.runner_command(kata_name, &curday_kata_path, out),
+    };
+    let _ = out.flush();
+
+    let command = cmd.as_ref().map(describe_command);
+    let (status, stdout, stderr) = match cmd {
+        None => (RunStatus::Skipped, Vec::new(), Vec::new()),
+        Some(cmd) => execute_kata(cmd, &ctx.limits(kata_name), out, mode),
+    };
+    let duration = start.elapsed();
+    if mode == OutputMode::Buffer {
+        let _ = out.write_all(&stdout);
+        let _ = out.write_all(&stderr);
+    }
+
+    let stdout = String::from_utf8_lossy(&stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&stderr).into_owned();
+    let tests = match status {
+        RunStatus::Skipped => None,
+        _ => ctx
+            .parser(kata_name)
+            .parse(&format!("{}{}", stdout, stderr), &curday_kata_path),
+    };
+
+    let result = RunResult {
+        kata_name: kata_name.to_string(),
+        day: ctx.day,
+        day_name: ctx.workspace.days.name(ctx.day),
+        path: curday_kata_path,
+        command,
+        status,
+        duration,
+        stdout,
+        stderr,
+        tests,
+    };
+    if let Some((passed, total)) = result.tests_passed() {
+        let _ = writeln!(out, "\n{}: {}/{} tests passed", kata_name, passed, total);
+        for name in result.failed_tests() {
+            let _ = writeln!(out, "  FAIL {}", name);
+        }
     }
+    result
 }
 
-/// runs the kata in the given path
-fn run(curday_kata_path: PathBuf) -> Option<std::process::Child> {
-    if Command::new("make")
-        .arg("--version")
-        .stdout(std::process::Stdio::null())
-        .status()
-        .is_ok()
-    {
-        return run_make_command(curday_kata_path);
-    }
-    run_os_command(curday_kata_path)
-}
-
-/// runs the kata in the given path using the make command
-fn run_make_command(path: PathBuf) -> Option<std::process::Child> {
-    let makefile_path = path.join("Makefile");
-
-    if !makefile_path.exists() {
-        // Check if this is an embedded example kata
-        if let Some(kata_name) = path.file_name().and_then(|n| n.to_str()) {
-            if let Some((language, _)) = is_embedded_kata(kata_name) {
-                if let Some(run_cmd) = get_embedded_run_command(&language, kata_name) {
-                    println!("Running embedded kata command: {}", run_cmd);
-                    // Parse and execute the command
-                    let mut parts = run_cmd.split_whitespace();
-                    if let Some(cmd) = parts.next() {
-                        return Some(
-                            Command::new(cmd)
-                                .args(parts)
-                                .current_dir(path)
-                                .stdout(std::process::Stdio::inherit())
-                                .stderr(std::process::Stdio::inherit())
-                                .spawn()
-                                .expect("failed to run the kata"),
-                        );
+/// returns the command line of a command, quoting the arguments that contain whitespace
+fn describe_command(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            } else {
+                arg.into_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// spawns the command of a kata and waits for it, killing it if it exceeds its timeout.
+/// With a timeout the kata runs in its own process group, so that everything it started (e.g.
+/// the test binary behind `make run`) is killed along with it. Unless it runs in parallel, that
+/// group gets the terminal while it runs, so the kata gets Ctrl-C and can read stdin.
+/// Unless the output is inherited, returns the stdout and stderr of the kata.
+fn execute_kata(
+    mut cmd: Command,
+    limits: &KataLimits,
+    out: &mut dyn Write,
+    mode: OutputMode,
+) -> (RunStatus, Vec<u8>, Vec<u8>) {
+    if mode != OutputMode::Inherit {
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+
+    #[cfg(unix)]
+    let group = match limits.timeout {
+        Some(_) => process_group::GroupSlot::reserve(),
+        None => None,
+    };
+    #[cfg(unix)]
+    let foreground =
+        group.is_some() && mode != OutputMode::Buffer && process_group::owns_terminal();
+    #[cfg(unix)]
+    if group.is_some() {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+        if foreground {
+            process_group::give_terminal(&mut cmd);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    apply_resource_limits(&mut cmd, limits);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = writeln!(out, "Error: failed to run the kata: {}", e);
+            return (RunStatus::Failed(None), Vec::new(), Vec::new());
+        }
+    };
+    #[cfg(unix)]
+    if let Some(group) = &group {
+        group.set(child.id());
+    }
+
+    // drain the pipes while waiting, otherwise a chatty kata blocks on a full pipe
+    let read_pipe = |pipe: Option<Box<dyn Read + Send>>, mut tee: Option<Box<dyn Write + Send>>| {
+        pipe.map(|mut pipe| {
+            std::thread::spawn(move || {
+                let mut buf = Vec::new();
+                let mut chunk = [0; 8192];
+                while let Ok(n) = pipe.read(&mut chunk) {
+                    if n == 0 {
+                        break;
                     }
+                    if let Some(tee) = tee.as_mut() {
+                        let _ = tee.write_all(&chunk[..n]);
+                        let _ = tee.flush();
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
                 }
+                buf
+            })
+        })
+    };
+    let tee = mode == OutputMode::Tee;
+    let stdout = read_pipe(
+        child.stdout.take().map(|p| Box::new(p) as _),
+        tee.then(|| Box::new(std::io::stdout()) as _),
+    );
+    let stderr = read_pipe(
+        child.stderr.take().map(|p| Box::new(p) as _),
+        tee.then(|| Box::new(std::io::stderr()) as _),
+    );
+
+    let status = wait_with_timeout(&mut child, limits.timeout);
+    #[cfg(unix)]
+    if foreground {
+        process_group::take_terminal();
+        // katac would have got the Ctrl-C too if it had stayed in the foreground
+        use std::os::unix::process::ExitStatusExt;
+        if let Ok(Some(status)) = &status {
+            if status.signal() == Some(libc::SIGINT) {
+                process_group::interrupt();
             }
         }
+    }
+
+    let join = |reader: Option<std::thread::JoinHandle<Vec<u8>>>| {
+        reader.and_then(|r| r.join().ok()).unwrap_or_default()
+    };
+    let (stdout, stderr) = (join(stdout), join(stderr));
+
+    let status = match status {
+        Ok(Some(status)) => exit_status_to_run_status(status),
+        Ok(None) => {
+            let _ = writeln!(
+                out,
+                "Timed out after {}s",
+                limits.timeout.unwrap_or_default().as_secs()
+            );
+            RunStatus::TimedOut
+        }
+        Err(e) => {
+            let _ = writeln!(out, "Error: failed to wait on the kata: {}", e);
+            RunStatus::Failed(None)
+        }
+    };
+    (status, stdout, stderr)
+}
+
+/// waits for the child to exit, returns None if it was killed because it ran past the timeout
+fn wait_with_timeout(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> std::io::Result<Option<ExitStatus>> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return child.wait().map(Some),
+    };
 
-        println!("No Makefile found in {}", path.display());
-        return None;
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if start.elapsed() >= timeout {
+            kill_process_tree(child);
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
     }
+}
 
-    Some(
-        Command::new("make")
-            .arg("run")
-            .arg("-s")
-            .current_dir(path)
-            .stdout(std::process::Stdio::inherit())
-            .stderr(std::process::Stdio::inherit())
-            .spawn()
-            .expect("failed to run the kata"),
-    )
+/// kills the child and every process it started
+fn kill_process_tree(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        // when the child leads its own process group, see execute_kata
+        let pid = child.id() as libc::pid_t;
+        // SAFETY: getpgid and kill have no memory safety requirements
+        unsafe {
+            if libc::getpgid(pid) == pid {
+                libc::kill(-pid, libc::SIGKILL);
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &child.id().to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+
+    let _ = child.kill();
 }
 
-/// runs the kata in the given path using an OS specific file (run.sh or run.bat)
-fn run_os_command(run_path: PathBuf) -> Option<std::process::Child> {
-    if cfg!(target_os = "windows") {
-        let bat_file = run_path.join("run.bat");
-        if !bat_file.exists() {
-            println!("No run.bat file found in {}", run_path.display());
-            return None;
+/// limits the memory and CPU time of the kata with rlimits, inherited by everything it starts
+#[cfg(target_os = "linux")]
+fn apply_resource_limits(cmd: &mut Command, limits: &KataLimits) {
+    use std::os::unix::process::CommandExt;
+
+    let max_memory = limits
+        .max_memory_mb
+        .map(|mb| mb.saturating_mul(1024 * 1024));
+    let max_cpu = limits.max_cpu_secs;
+    if max_memory.is_none() && max_cpu.is_none() {
+        return;
+    }
+
+    // SAFETY: setrlimit is async-signal-safe, so it can be called between fork and exec
+    unsafe {
+        cmd.pre_exec(move || {
+            let set_limit = |resource, value: u64| {
+                let limit = libc::rlimit {
+                    rlim_cur: value as libc::rlim_t,
+                    rlim_max: value as libc::rlim_t,
+                };
+                if libc::setrlimit(resource, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            };
+            if let Some(bytes) = max_memory {
+                set_limit(libc::RLIMIT_AS, bytes)?;
+            }
+            if let Some(secs) = max_cpu {
+                set_limit(libc::RLIMIT_CPU, secs)?;
+            }
+            Ok(())
+        });
+    }
+}
+
+/// converts the exit status of a kata's run command into a RunStatus
+fn exit_status_to_run_status(status: ExitStatus) -> RunStatus {
+    if status.success() {
+        RunStatus::Passed
+    } else {
+        RunStatus::Failed(status.code())
+    }
+}
+
+/// how often the kata folders are checked for changes in watch mode
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// how long the kata folders must stay unchanged before re-running, so bursts of saves run once
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+/// folders that are not watched nor diffed, since builds and test runs write into them
+const ARTIFACT_DIRS: [&str; 4] = [".git", "__pycache__", "node_modules", "target"];
+
+/// modification time of every file in a kata folder
+type Snapshot = HashMap<PathBuf, SystemTime>;
+
+/// watches the katas of the current day and re-runs the ones whose files changed, never returns
+fn watch_katas(ctx: &RunContext, kata_names: &[String], on_event: &mut dyn FnMut(RunEvent)) -> ! {
+    let snapshot_kata =
+        |kata_name: &str| snapshot_dir(&ctx.workspace.days.path(ctx.day).join(kata_name));
+
+    let mut snapshots: HashMap<&str, Snapshot> = kata_names
+        .iter()
+        .map(|k| (k.as_str(), snapshot_kata(k)))
+        .collect();
+    let mut changed: HashSet<&str> = HashSet::new();
+    let mut last_change = Instant::now();
+
+    on_event(RunEvent::Watching {
+        katas: kata_names.len(),
+    });
+    loop {
+        std::thread::sleep(WATCH_POLL_INTERVAL);
+
+        for kata_name in kata_names {
+            let snapshot = snapshot_kata(kata_name);
+            if snapshots.get(kata_name.as_str()) != Some(&snapshot) {
+                snapshots.insert(kata_name, snapshot);
+                changed.insert(kata_name);
+                last_change = Instant::now();
+            }
         }
 
-        return Some(
-            Command::new("cmd")
-                .arg("/C")
-                .arg("run.bat")
-                .current_dir(&run_path)
-                .stdout(std::process::Stdio::inherit())
-                .stderr(std::process::Stdio::inherit())
-                .spawn()
-                .expect("failed to run the kata"),
-        );
+        if changed.is_empty() || last_change.elapsed() < WATCH_DEBOUNCE {
+            continue;
+        }
+
+        // keep the order the katas were given in
+        let to_run: Vec<String> = kata_names
+            .iter()
+            .filter(|k| changed.contains(k.as_str()))
+            .cloned()
+            .collect();
+        changed.clear();
+
+        on_event(RunEvent::Changed(&to_run));
+        let results = run_kata_batch(ctx, &to_run, on_event);
+        if let Err(e) = report_run_results(ctx, &results, on_event) {
+            warn!("{}", e);
+        }
+
+        // files written by the run itself (build outputs, caches) should not trigger another run
+        for kata_name in kata_names.iter().filter(|k| to_run.contains(k)) {
+            snapshots.insert(kata_name, snapshot_kata(kata_name));
+        }
+
+        on_event(RunEvent::Watching {
+            katas: kata_names.len(),
+        });
+    }
+}
+
+/// returns the modification time of every file inside dir, recursively
+fn snapshot_dir(dir: &Path) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return snapshot,
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            if ARTIFACT_DIRS.contains(&basename(&path).as_str()) {
+                continue;
+            }
+            snapshot.extend(snapshot_dir(&path));
+        } else if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+            snapshot.insert(path, modified);
+        }
+    }
+    snapshot
+}
+
+/// record of the stats, printed with --format json or ndjson
+#[derive(Serialize, Debug)]
+pub struct StatsRecord {
+    /// `history`, or `days` when there was no history and the days folder was scanned instead
+    pub source: &'static str,
+    #[serde(flatten)]
+    pub stats: Stats,
+}
+
+/// returns the records of the history and `history`. Without a history yet, returns records of
+/// the katas in the days folder and `days`
+fn practice_records(workspace: &Workspace) -> Result<(Vec<HistoryRecord>, &'static str), Error> {
+    let history = &workspace.history;
+    let records = history.read().map_err(Error::io(format!(
+        "failed to read the history in {}",
+        history.path().display()
+    )))?;
+    if records.is_empty() {
+        return Ok((day_folder_records(&workspace.days), "days"));
+    }
+    Ok((records, "history"))
+}
+
+/// returns how often and how well each kata was practiced, with totals per day and per
+/// language. Without a history yet, the katas in the days folder are counted instead
+fn compute_stats(workspace: &Workspace, filter: &StatsFilter) -> Result<StatsRecord, Error> {
+    let (records, source) = practice_records(workspace)?;
+    Ok(StatsRecord {
+        source,
+        stats: stats::compute(&records, filter),
+    })
+}
+
+/// record of the streaks, printed with --format json or ndjson
+#[derive(Serialize, Debug)]
+pub struct StreakRecord {
+    /// `history`, or `days` when there was no history and the days folder was scanned instead
+    pub source: &'static str,
+    pub current_streak: Option<Streak>,
+    pub longest_streak: Option<Streak>,
+    /// katas completed each day shown in the heatmap
+    pub days: Vec<DayCompleted>,
+    /// heatmap of the katas completed each day, one column per week
+    #[serde(skip)]
+    pub heatmap: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DayCompleted {
+    pub date: NaiveDate,
+    pub completed: usize,
+}
+
+/// returns the current and longest daily streaks and a heatmap of the last `weeks` weeks
+fn compute_streak(workspace: &Workspace, weeks: u32) -> Result<StreakRecord, Error> {
+    let (records, source) = practice_records(workspace)?;
+    let completed = streak::completed_per_day(&records);
+    let today = Local::now().date_naive();
+    let streaks = streak::streaks(&completed, today);
+
+    let first_day = today - chrono::Duration::weeks(weeks as i64);
+    Ok(StreakRecord {
+        source,
+        current_streak: streaks.current,
+        longest_streak: streaks.longest,
+        days: completed
+            .range(first_day..)
+            .map(|(&date, &completed)| DayCompleted { date, completed })
+            .collect(),
+        heatmap: streak::heatmap(&completed, today, weeks),
+    })
+}
+
+/// returns the paths of the kata folders in a day, by name
+fn day_kata_paths(day_path: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(day_path) {
+        Ok(katas) => katas
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths
+}
+
+/// returns a start record for every kata in the days folder, dated by the kata folder's mtime
+fn day_folder_records(days: &Days) -> Vec<HistoryRecord> {
+    let mut records = Vec::new();
+    for (day, day_path) in days.days() {
+        for path in day_kata_paths(&day_path) {
+            let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+                Ok(modified) => modified,
+                Err(_) => continue,
+            };
+            records.push(HistoryRecord {
+                timestamp: chrono::DateTime::<Local>::from(modified).fixed_offset(),
+                language: kata_language(&path),
+                ..HistoryRecord::new(HistoryEvent::Start, day, &basename(&path), None)
+            });
+        }
+    }
+    records
+}
+
+/// a dayN folder renamed by `katac migrate-days`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayMove {
+    /// name of the folder, e.g. `day3`
+    pub from: String,
+    /// its new name, e.g. `2026-10-17`
+    pub to: String,
+}
+
+/// renames the dayN folders to the day scheme `to`, or the one of the config file, dating each
+/// day by its folder's mtime. Days of the same date are merged, and the days of the history are
+/// renumbered to match. Returns the days that were moved, or would be with `dry_run`, none if
+/// there are no dayN folders
+fn migrate_days(
+    workspace: &Workspace,
+    to: Option<DayScheme>,
+    dry_run: bool,
+) -> Result<Vec<DayMove>, Error> {
+    let configured = &workspace.days;
+    let scheme = to.unwrap_or(configured.scheme());
+    if scheme == DayScheme::Numbered {
+        return Err(Error::InvalidInput(format!(
+            "pass the day scheme to migrate to with --to, or set day_scheme in {}",
+            CONFIG_FILE_NAME
+        )));
+    }
+    let today = Local::now().date_naive();
+    let numbered = Days::new(configured.dir(), DayScheme::Numbered, today);
+    let target = Days::new(configured.dir(), scheme, today);
+
+    let mut moved = Vec::new();
+    let mut renumbered: HashMap<u32, u32> = HashMap::new();
+    let mut errors = Vec::new();
+    for (day, path) in numbered.days() {
+        let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(e) => {
+                errors.push(Error::io(format!(
+                    "failed to read the mtime of {}",
+                    path.display()
+                ))(e));
+                continue;
+            }
+        };
+        let new_day = days::date_number(chrono::DateTime::<Local>::from(modified).date_naive());
+        let day_move = DayMove {
+            from: numbered.name(day),
+            to: target.name(new_day),
+        };
+        if dry_run {
+            moved.push(day_move);
+            continue;
+        }
+        match move_day(&path, &target.path(new_day)) {
+            Ok(()) => {
+                renumbered.insert(day, new_day);
+                moved.push(day_move);
+            }
+            Err(e) => errors.push(Error::io(format!(
+                "failed to move {} to {}",
+                numbered.name(day),
+                target.name(new_day)
+            ))(e)),
+        }
+    }
+
+    if !renumbered.is_empty() {
+        let history = &workspace.history;
+        let records = history.read().map(|records| {
+            records
+                .into_iter()
+                .map(|r| HistoryRecord {
+                    day: renumbered.get(&r.day).copied().unwrap_or(r.day),
+                    ..r
+                })
+                .collect::<Vec<_>>()
+        });
+        if let Err(e) = records.and_then(|records| history.write(&records)) {
+            errors.push(Error::io(format!(
+                "failed to renumber the days of the history in {}",
+                history.path().display()
+            ))(e));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::MigrateFailed { moved, errors });
+    }
+    Ok(moved)
+}
+
+/// moves a day folder to a new path. If a day is already there, moves the katas into it unless
+/// one of them is already there too
+fn move_day(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !to.exists() {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        return fs::rename(from, to);
+    }
+
+    let katas: Vec<fs::DirEntry> = fs::read_dir(from)?.filter_map(|e| e.ok()).collect();
+    if let Some(kata) = katas.iter().find(|k| to.join(k.file_name()).exists()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} is in both days", kata.file_name().to_string_lossy()),
+        ));
+    }
+    for kata in katas {
+        fs::rename(kata.path(), to.join(kata.file_name()))?;
+    }
+    fs::remove_dir(from)
+}
+
+/// what `katac list` lists
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    /// the katas of the katas folder
+    Katas,
+    /// the days and their katas
+    Days,
+    /// the example katas `katac init` copies from
+    Templates,
+}
+
+/// filters of `katac list`
+#[derive(Debug, Clone, Default)]
+pub struct ListFilter {
+    /// only katas with this tag in their kata.toml
+    pub tag: Option<String>,
+    /// only katas in this language
+    pub language: Option<String>,
+}
+
+impl ListFilter {
+    fn matches(&self, language: Option<&str>, manifest: &KataManifest) -> bool {
+        self.language.as_ref().is_none_or(|wanted| {
+            language.is_some_and(|language| language.eq_ignore_ascii_case(wanted))
+        }) && self
+            .tag
+            .as_ref()
+            .is_none_or(|tag| manifest.tags.contains(tag))
     }
+}
+
+/// record of a kata of the katas folder, printed by `katac list katas` with --format json or
+/// ndjson
+#[derive(Serialize, Debug, Clone)]
+pub struct KataListRecord {
+    pub kata: String,
+    pub path: String,
+    pub language: Option<String>,
+    pub description: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    pub tags: Vec<String>,
+    pub estimated_minutes: Option<u32>,
+    /// number of days the kata was practiced
+    pub times_practiced: usize,
+    pub last_practiced: Option<NaiveDate>,
+}
+
+/// record of a day, printed by `katac list days` with --format json or ndjson
+#[derive(Serialize, Debug, Clone)]
+pub struct DayListRecord {
+    pub day: u32,
+    /// name of the day folder, e.g. `day3` or `2026-10-17`
+    pub name: String,
+    pub path: String,
+    pub katas: Vec<DayKataRecord>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DayKataRecord {
+    pub kata: String,
+    pub language: Option<String>,
+    /// `passed` or `failed` as of the last `katac run`, None if it was never run
+    pub status: Option<&'static str>,
+}
+
+/// record of an example kata, printed by `katac list templates` with --format json or ndjson
+#[derive(Serialize, Debug, Clone)]
+pub struct TemplateRecord {
+    pub language: String,
+    pub kata: String,
+    /// `embedded`, or the examples folder it is in
+    pub source: String,
+    pub description: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    pub tags: Vec<String>,
+}
+
+/// what `katac list` found
+#[derive(Debug, Clone)]
+pub enum Listing {
+    Katas(Vec<KataListRecord>),
+    Days(Vec<DayListRecord>),
+    Templates(Vec<TemplateRecord>),
+}
 
-    let sh_file = run_path.join("run.sh");
-    if !sh_file.exists() {
-        println!("No run.sh file found in {}", run_path.display());
-        return None;
+/// lists the katas of the katas folder, the days or the example templates
+fn list(
+    workspace: &Workspace,
+    kind: ListKind,
+    filter: &ListFilter,
+    source: &Option<String>,
+) -> Result<Listing, Error> {
+    Ok(match kind {
+        ListKind::Katas => Listing::Katas(list_katas(workspace, filter)?),
+        ListKind::Days => Listing::Days(list_days(workspace, filter)?),
+        ListKind::Templates => Listing::Templates(list_templates(workspace, filter, source)?),
+    })
+}
+
+/// lists the katas of the katas folder with their kata.toml and when they were last practiced
+fn list_katas(workspace: &Workspace, filter: &ListFilter) -> Result<Vec<KataListRecord>, Error> {
+    let katas_dir = &workspace.katas_dir;
+    let mut katas = katas(katas_dir)?;
+    let (records, _) = practice_records(workspace)?;
+
+    katas.sort_by(|a, b| a.name.cmp(&b.name));
+    let records: Vec<KataListRecord> = katas
+        .into_iter()
+        .filter_map(|kata| {
+            let path = kata_path(&kata.name, katas_dir);
+            let language = kata_language(&path);
+            if !filter.matches(language.as_deref(), &kata.manifest) {
+                return None;
+            }
+            let practiced: Vec<&HistoryRecord> =
+                records.iter().filter(|r| r.kata == kata.name).collect();
+            let days: HashSet<u32> = practiced.iter().map(|r| r.day).collect();
+            Some(KataListRecord {
+                path: path.display().to_string(),
+                language,
+                description: kata.manifest.description,
+                difficulty: kata.manifest.difficulty,
+                tags: kata.manifest.tags,
+                estimated_minutes: kata.manifest.estimated_minutes,
+                times_practiced: days.len(),
+                last_practiced: practiced.iter().map(|r| r.timestamp.date_naive()).max(),
+                kata: kata.name,
+            })
+        })
+        .collect();
+    Ok(records)
+}
+
+/// lists every day with its katas and how their last run went
+fn list_days(workspace: &Workspace, filter: &ListFilter) -> Result<Vec<DayListRecord>, Error> {
+    let days = &workspace.days;
+    // the last run of each kata of each day
+    let mut last_runs: HashMap<(u32, &str), &HistoryRecord> = HashMap::new();
+    let (history_records, _) = practice_records(workspace)?;
+    for record in history_records
+        .iter()
+        .filter(|r| r.event == HistoryEvent::Run)
+    {
+        let last = last_runs
+            .entry((record.day, record.kata.as_str()))
+            .or_insert(record);
+        if record.timestamp >= last.timestamp {
+            *last = record;
+        }
     }
 
-    Some(
-        Command::new("sh")
-            .arg("./run.sh")
-            .current_dir(&run_path)
-            .stdout(std::process::Stdio::inherit())
-            .stderr(std::process::Stdio::inherit())
-            .spawn()
-            .expect("failed to run the kata"),
-    )
+    let records: Vec<DayListRecord> = days
+        .days()
+        .into_iter()
+        .filter_map(|(day, day_path)| {
+            let katas: Vec<DayKataRecord> = day_kata_paths(&day_path)
+                .iter()
+                .filter_map(|path| {
+                    let kata = basename(path);
+                    let language = kata_language(path);
+                    if !filter.matches(language.as_deref(), &kata_manifest(path)) {
+                        return None;
+                    }
+                    let status = last_runs
+                        .get(&(day, kata.as_str()))
+                        .map(|r| match r.passed {
+                            Some(true) => "passed",
+                            _ => "failed",
+                        });
+                    Some(DayKataRecord {
+                        kata,
+                        language,
+                        status,
+                    })
+                })
+                .collect();
+            let filtered = filter.tag.is_some() || filter.language.is_some();
+            if filtered && katas.is_empty() {
+                return None;
+            }
+            Some(DayListRecord {
+                day,
+                name: days.name(day),
+                path: day_path.display().to_string(),
+                katas,
+            })
+        })
+        .collect();
+    Ok(records)
+}
+
+/// returns the kata.toml of an embedded example kata, an empty one if it has none
+fn embedded_manifest(language: &str, kata_name: &str) -> KataManifest {
+    let path = format!("{}/{}/{}", language, kata_name, MANIFEST_FILE);
+    let content = match EXAMPLE_KATAS
+        .get_file(&path)
+        .and_then(|f| f.contents_utf8())
+    {
+        Some(content) => content,
+        None => return KataManifest::default(),
+    };
+    KataManifest::parse(content).unwrap_or_else(|e| {
+        warn!("ignoring invalid embedded {}: {}", path, e);
+        KataManifest::default()
+    })
+}
+
+/// lists the templates of the given source, or the one of the workspace, in its order
+fn list_templates(
+    workspace: &Workspace,
+    filter: &ListFilter,
+    source: &Option<String>,
+) -> Result<Vec<TemplateRecord>, Error> {
+    let mut opened = None;
+    let source = template_source(workspace, source, &mut opened);
+    let records: Vec<TemplateRecord> = source
+        .templates()?
+        .into_iter()
+        .filter_map(|template| {
+            let manifest = source.manifest(&template);
+            if !filter.matches(Some(&template.language), &manifest) {
+                return None;
+            }
+            Some(TemplateRecord {
+                source: source.name(),
+                language: template.language,
+                kata: template.kata,
+                description: manifest.description,
+                difficulty: manifest.difficulty,
+                tags: manifest.tags,
+            })
+        })
+        .collect();
+    Ok(records)
+}
+
+/// options of the random subcommand
+#[derive(Debug, Clone, Default)]
+pub struct RandomOptions {
+    /// how to pick the katas, overrides the config file
+    pub strategy: Option<RandomStrategy>,
+    /// seed of the random picks, a random one if None
+    pub seed: Option<u64>,
+    /// use today's date as the seed
+    pub seed_from_date: bool,
+    /// name of the pool to pick from, the random list or the katas folder if None
+    pub pool: Option<String>,
+}
+
+impl RandomOptions {
+    /// returns the seed of the random picks: the given one, today's date as YYYYMMDD,
+    /// or a random one
+    fn seed(&self) -> u64 {
+        if let Some(seed) = self.seed {
+            return seed;
+        }
+        if self.seed_from_date {
+            let today = Local::now().date_naive();
+            return today
+                .format("%Y%m%d")
+                .to_string()
+                .parse()
+                .unwrap_or_default();
+        }
+        thread_rng().gen()
+    }
+}
+
+/// katas picked by `katac random`
+#[derive(Debug, Clone)]
+pub struct RandomPicks {
+    pub katas: Vec<String>,
+    /// seed of the picks, the same seed picks the same katas
+    pub seed: u64,
+    /// why each kata was picked, with the spaced strategy
+    pub schedule: Vec<Pick>,
 }
 
-/// returns a vector of random katas from the katas.toml file or the katas folder
-pub fn random_katas(args: &Args, number_of_katas: u8) -> Vec<String> {
+/// picks random katas from a pool of the katas.toml file, its random list or the katas folder
+fn random_katas(
+    workspace: &Workspace,
+    number_of_katas: u8,
+    options: &RandomOptions,
+) -> Result<RandomPicks, Error> {
     if number_of_katas == 0 {
-        eprintln!("Error: number of katas must be greater than 0");
-        std::process::exit(1);
+        return Err(Error::InvalidInput(
+            "number of katas must be greater than 0".to_string(),
+        ));
     }
 
-    let config_file = match args.config.as_ref() {
-        Some(config_file) => config_file.as_str(),
-        None => CONFIG_FILE_NAME,
+    let seed = options.seed();
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let config = &workspace.config;
+    let mut pool = random_pool(workspace, options.pool.as_deref())?;
+
+    let (records, _) = practice_records(workspace)?;
+    let today = Local::now().date_naive();
+    let excluded = pool.exclude_recent(&records, today);
+    if !excluded.is_empty() {
+        info!("left out katas done recently: {}", excluded.join(", "));
+    }
+
+    let strategy = options
+        .strategy
+        .or(config.katas.strategy)
+        .unwrap_or_default();
+    let mut schedule = Vec::new();
+    let picked = match strategy {
+        RandomStrategy::Uniform => {
+            pool.select(number_of_katas as usize, pools::weighted_pick(&mut rng))
+        }
+        RandomStrategy::Spaced => {
+            // shuffle first so equally urgent katas are picked at random
+            pool.candidates.shuffle(&mut rng);
+            let names: Vec<String> = pool.candidates.iter().map(|c| c.name.clone()).collect();
+            let ranked = schedule::pick_spaced(&names, &records, today, names.len());
+            pool.candidates
+                .sort_by_key(|c| ranked.iter().position(|p| p.kata == c.name));
+            // the most urgent of the eligible katas
+            pool.select(number_of_katas as usize, |_| 0)
+                .inspect(|picked| {
+                    schedule = ranked
+                        .into_iter()
+                        .filter(|p| picked.contains(&p.kata))
+                        .collect();
+                })
+        }
+    };
+
+    let katas = picked.map_err(|e| match excluded.is_empty() {
+        true => Error::Pool(e),
+        false => Error::Pool(format!(
+            "{} (left out {}, done in the last {} day(s))",
+            e,
+            excluded.join(", "),
+            pool.exclude_days.unwrap_or_default()
+        )),
+    })?;
+    Ok(RandomPicks {
+        katas,
+        seed,
+        schedule,
+    })
+}
+
+/// returns the pool katac random picks from: the named pool, the random list of the config
+/// file, or every kata in the katas folder
+fn random_pool(workspace: &Workspace, pool_name: Option<&str>) -> Result<Pool, Error> {
+    let (katas_dir, config) = (&workspace.katas_dir, &workspace.config);
+    let folder_katas = || -> Result<Vec<(PoolKata, KataManifest)>, Error> {
+        let mut katas = katas(katas_dir)?;
+        if katas.is_empty() {
+            return Err(Error::Pool(
+                "no katas found in the katas folder".to_string(),
+            ));
+        }
+        // read_dir order differs between machines, sort so a seed picks the same katas anywhere
+        katas.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(katas
+            .into_iter()
+            .map(|k| (PoolKata::Name(k.name), k.manifest))
+            .collect())
+    };
+    let with_manifests = |katas: &[PoolKata]| -> Vec<(PoolKata, KataManifest)> {
+        katas
+            .iter()
+            .map(|k| (k.clone(), kata_manifest(&kata_path(k.name(), katas_dir))))
+            .collect()
     };
 
-    let mut kata_names: Vec<String>;
-    if std::path::Path::new(&config_file).exists() {
-        kata_names = read_random_katas_from_config_file(config_file.to_string());
-        if number_of_katas > kata_names.len() as u8 {
-            eprintln!(
-                "Error: random number ({}) is higher than the number of katas found ({}) in the katas.toml file",
-                number_of_katas,
-                kata_names.len()
-            );
-            std::process::exit(1);
-        }
-    } else {
-        info!("no katas.toml found, reading katas folder for random katas");
-        // kata_names becomes all files inside the katas folder
-        kata_names = katas(&katas_dir(args));
-        if kata_names.is_empty() {
-            eprintln!("Error: no katas found in the katas folder");
-            std::process::exit(1);
-        }
-        if number_of_katas > kata_names.len() as u8 {
-            eprintln!(
-                "Error: random number ({}) is higher than the number of katas found ({}) in the katas folder",
-                number_of_katas,
-                kata_names.len()
-            );
-            std::process::exit(1);
+    let (description, katas, require, exclude_days) = match pool_name {
+        Some(name) => {
+            let pool = config.pools.get(name).ok_or_else(|| {
+                let mut names: Vec<&str> = config.pools.keys().map(|k| k.as_str()).collect();
+                names.sort();
+                Error::Pool(match names.is_empty() {
+                    true => format!(
+                        "pool '{}' not found, there are no [pools] in the config file",
+                        name
+                    ),
+                    false => format!(
+                        "pool '{}' not found, the pools are: {}",
+                        name,
+                        names.join(", ")
+                    ),
+                })
+            })?;
+            let katas = match pool.katas.is_empty() {
+                true => folder_katas()?,
+                false => with_manifests(&pool.katas),
+            };
+            (
+                format!("pool '{}'", name),
+                katas,
+                pool.require.clone(),
+                pool.exclude_days.or(config.katas.exclude_days),
+            )
         }
-        kata_names.shuffle(&mut thread_rng());
-    }
-    kata_names[0..number_of_katas as usize].to_vec()
+        None => match &config.katas.random {
+            Some(random) if random.is_empty() => {
+                return Err(Error::Pool(
+                    "the random list of the config file is empty".to_string(),
+                ))
+            }
+            Some(random) => (
+                "the random list of the config file".to_string(),
+                with_manifests(random),
+                Vec::new(),
+                config.katas.exclude_days,
+            ),
+            None => (
+                "the katas folder".to_string(),
+                folder_katas()?,
+                Vec::new(),
+                config.katas.exclude_days,
+            ),
+        },
+    };
+
+    let candidates = katas
+        .into_iter()
+        .map(|(kata, manifest)| {
+            let kata_config = config.kata.get(kata.name());
+            // tags of the config file and of the kata.toml
+            let mut tags = kata_config.map(|k| k.tags.clone()).unwrap_or_default();
+            for tag in manifest.tags {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            Candidate::new(&kata, kata_config.and_then(|k| k.weight), tags)
+        })
+        .collect();
+    Ok(Pool {
+        description,
+        candidates,
+        require,
+        exclude_days,
+    })
 }
 
-/// creates a new kata in the kata_dir folder or the given path
-pub fn new_kata(args: &Args, kata_name: &str) {
+/// creates a new kata in the kata_dir folder or the given path, returns its path
+fn new_kata(workspace: &Workspace, kata_name: &str) -> Result<PathBuf, Error> {
     // Validate kata name if it's not a path
     if !kata_name.contains('/') {
-        if kata_name.is_empty() {
-            eprintln!("Error: kata name cannot be empty");
-            std::process::exit(1);
-        }
-        if kata_name.contains("..") {
-            eprintln!("Error: kata name cannot contain '..'");
-            std::process::exit(1);
-        }
-        if kata_name.starts_with('.') {
-            eprintln!("Error: kata name cannot start with '.'");
-            std::process::exit(1);
-        }
+        validate_kata_name(kata_name)?;
     }
 
-    let kata_dir = &katas_dir(args);
+    let kata_dir = &workspace.katas_dir;
     let kata_path = kata_path(kata_name, kata_dir);
     if kata_path.exists() {
-        println!("Kata {} already exists", kata_name);
-        std::process::exit(1);
+        return Err(Error::KataExists {
+            kata: kata_name.to_string(),
+            location: dirname(&kata_path),
+        });
     }
-    fs::create_dir_all(&kata_path).expect("failed to create the kata folder");
-    println!("{} created in {}.", kata_name, dirname(&kata_path));
+    fs::create_dir_all(&kata_path).map_err(Error::io(format!(
+        "failed to create the kata folder {}",
+        kata_path.display()
+    )))?;
 
-    if Command::new("make")
+    let created = if Command::new("make")
         .arg("--version")
         .stdout(std::process::Stdio::null())
         .status()
         .is_ok()
     {
-        create_makefile(kata_path);
+        create_makefile(kata_path.clone())
     } else {
-        create_os_run_file(kata_path);
-    }
+        create_os_run_file(kata_path.clone())
+    };
+    created.map_err(Error::io(format!(
+        "failed to create the run file of {}",
+        kata_name
+    )))?;
+    Ok(kata_path)
 }
 
 /// creates a new Makefile in the given path
-fn create_makefile(mut path: PathBuf) {
+fn create_makefile(mut path: PathBuf) -> std::io::Result<()> {
     let content = "run:\n\t@echo \"TODO: add your run command here\"";
     path.push("Makefile");
-    let mut f = fs::File::create(path).expect("failed to create the Makefile");
+    let mut f = fs::File::create(path)?;
     f.write_all(content.as_bytes())
-        .expect("failed to write to the Makefile");
 }
 
 /// creates a new run.sh or run.bat file in the given path
-fn create_os_run_file(mut kata_path: PathBuf) {
+fn create_os_run_file(mut kata_path: PathBuf) -> std::io::Result<()> {
     if cfg!(target_os = "windows") {
         let content = "TODO: add your run command here";
         kata_path.push("run.bat");
-        let mut f =
-            std::fs::File::create(kata_path).expect("failed to create the windows run file");
-        f.write_all(content.as_bytes())
-            .expect("failed to write to the windows run.bat file");
-        return;
+        let mut f = std::fs::File::create(kata_path)?;
+        return f.write_all(content.as_bytes());
     }
 
     let content = "#!/usr/bin/env bash\n\n# TODO: replace this line with  your run command (example: npm run test)";
     kata_path.push("run.sh");
-    let mut f = std::fs::File::create(&kata_path).expect("failed to create the linux run file");
+    let mut f = std::fs::File::create(&kata_path)?;
 
-    f.write_all(content.as_bytes())
-        .expect("failed to write to the linux run.sh file");
+    f.write_all(content.as_bytes())?;
 
     #[cfg(unix)]
     {
         use std::os::unix::prelude::PermissionsExt;
-        std::fs::set_permissions(kata_path, fs::Permissions::from_mode(0o755))
-            .expect("failed to set permissions on the linux run file");
+        std::fs::set_permissions(kata_path, fs::Permissions::from_mode(0o755))?;
     }
+    Ok(())
 }
 
-/// returns a vector of katas from the katas folder
-fn katas(katas_dir: &str) -> Vec<String> {
-    fs::read_dir(katas_dir)
-        .expect("Unable to read katas folder")
+/// a kata of the katas folder
+#[derive(Debug, Clone)]
+struct Kata {
+    name: String,
+    manifest: KataManifest,
+}
+
+/// returns the katas of the katas folder, with their kata.toml
+fn katas(katas_dir: &str) -> Result<Vec<Kata>, Error> {
+    let entries = fs::read_dir(katas_dir).map_err(Error::io(format!(
+        "failed to read the katas folder {}",
+        katas_dir
+    )))?;
+    Ok(entries
         .filter_map(|e| e.ok())
-        .filter_map(|e| e.file_name().into_string().ok())
-        .collect()
+        .filter_map(|e| {
+            let manifest = kata_manifest(&e.path());
+            e.file_name()
+                .into_string()
+                .ok()
+                .map(|name| Kata { name, manifest })
+        })
+        .collect())
 }
 
 /// returns a vector of katas from the current day folder
-fn curday_katas(curday_path: PathBuf) -> Vec<String> {
-    fs::read_dir(curday_path)
-        .expect("Unable to read current day contents")
+fn curday_katas(curday_path: PathBuf) -> Result<Vec<String>, Error> {
+    let entries = fs::read_dir(&curday_path).map_err(Error::io(format!(
+        "failed to read the katas of the current day {}",
+        curday_path.display()
+    )))?;
+    Ok(entries
         .filter_map(|e| e.ok())
         .filter_map(|e| e.file_name().into_string().ok())
-        .collect()
+        .collect())
 }
 
 /// returns the path of the given kata
@@ -536,514 +2652,175 @@ fn kata_path(kata_name: &str, katas_dir: &str) -> PathBuf {
     PathBuf::from(format!("{}/{}", katas_dir, kata_name))
 }
 
-/// returns the path of the current day
-fn curday_path(days_dir: &str) -> PathBuf {
-    PathBuf::from(format!("{}/day{}", days_dir, curday(days_dir)))
-}
-
-/// returns the path of the next day
-fn nextday_path(days_dir: &str) -> PathBuf {
-    PathBuf::from(format!("{}/day{}", days_dir, curday(days_dir) + 1))
-}
-
-/// returns the path of the given kata in the current day
-fn curday_kata_path(days_dir: &str, kata_name: &str) -> PathBuf {
-    curday_path(days_dir).join(kata_name)
-}
-
-/// reads the katas.toml file and returns a Data struct
-fn read_config_file(config_file_name: String) -> Data {
-    info!("Reading katas.toml file");
-
-    let str =
-        fs::read_to_string(config_file_name).expect("Something went wrong reading the config file");
-    toml::from_str(&str).expect("Something went wrong reading the config file")
-}
-
-/// reads the katas.toml file and returns a vector of random katas
-fn read_random_katas_from_config_file(config_file: String) -> Vec<String> {
-    let tom = read_config_file(config_file);
-
-    let mut kata_names = tom.katas.random.unwrap_or_default();
-    kata_names.shuffle(&mut thread_rng());
-    if kata_names.is_empty() {
-        println!("config file is empty");
-        std::process::exit(1);
-    }
-    kata_names
-}
-
-/// scans the embedded example katas and returns a list of (language, kata_name) tuples
-fn scan_embedded_katas() -> Vec<(String, String)> {
-    let mut katas = Vec::new();
-
-    // Iterate through language directories in embedded katas
-    for language_dir in EXAMPLE_KATAS.dirs() {
-        let language = language_dir
-            .path()
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("")
-            .to_string();
-
-        // Iterate through kata directories within each language
-        for kata_dir in language_dir.dirs() {
-            let kata_name = kata_dir
-                .path()
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("")
-                .to_string();
-
-            if !kata_name.is_empty() && !language.is_empty() {
-                katas.push((language.clone(), kata_name));
-            }
-        }
-    }
-
-    katas.sort_by(|a, b| {
-        // Sort by kata name first, then by language
-        match a.1.cmp(&b.1) {
-            std::cmp::Ordering::Equal => a.0.cmp(&b.0),
-            other => other,
-        }
-    });
-
-    katas
-}
-
 /// Checks if a kata is from embedded example-katas
 fn is_embedded_kata(kata_name: &str) -> Option<(String, String)> {
-    let available = scan_embedded_katas();
-    available.into_iter().find(|(_, name)| name == kata_name)
+    EmbeddedSource
+        .find(kata_name)
+        .ok()
+        .flatten()
+        .map(|t| (t.language, t.kata))
 }
 
-/// Extracts the run command from an embedded kata's Makefile
-fn get_embedded_run_command(language: &str, kata_name: &str) -> Option<String> {
+/// Extracts the recipe of the run target from an embedded kata's Makefile, one entry per line
+fn get_embedded_run_command(language: &str, kata_name: &str) -> Option<Vec<String>> {
     let makefile_path = format!("{}/{}/Makefile", language, kata_name);
     let makefile = EXAMPLE_KATAS.get_file(&makefile_path)?;
     let content = std::str::from_utf8(makefile.contents()).ok()?;
-
-    // Parse Makefile to extract run target command
-    for line in content.lines() {
-        let trimmed = line.trim();
-        // Skip the "run:" line, get the command (starts with tab)
-        if line.starts_with('\t') && !trimmed.is_empty() {
-            return Some(trimmed.to_string());
-        }
-    }
-    None
+    makefile_recipe(content, "run")
 }
 
-/// Copies the Makefile from an embedded kata if it doesn't exist
-fn ensure_makefile_exists(dest: &Path, language: &str, kata_name: &str) {
+/// Copies the Makefile from an embedded kata if it doesn't exist, returns true if it was created
+fn ensure_makefile_exists(dest: &Path, language: &str, kata_name: &str) -> bool {
     let makefile_dest = dest.join("Makefile");
 
     // Don't overwrite existing Makefile
     if makefile_dest.exists() {
-        return;
+        return false;
     }
 
     // Copy from embedded kata
     let makefile_path = format!("{}/{}/Makefile", language, kata_name);
-    if let Some(makefile) = EXAMPLE_KATAS.get_file(&makefile_path) {
-        if let Ok(()) = fs::write(&makefile_dest, makefile.contents()) {
-            println!("  → Created Makefile for {}", kata_name);
-        }
+    match EXAMPLE_KATAS.get_file(&makefile_path) {
+        Some(makefile) => fs::write(&makefile_dest, makefile.contents()).is_ok(),
+        None => false,
     }
 }
 
-/// Extracts unique language names from embedded katas
-fn get_available_languages() -> Vec<String> {
-    let mut languages: Vec<String> = EXAMPLE_KATAS
-        .dirs()
-        .filter_map(|dir| {
-            dir.path()
-                .file_name()
-                .and_then(|n| n.to_str())
-                .map(|s| s.to_string())
-        })
-        .collect();
-
-    languages.sort();
-    languages.dedup();
-    languages
-}
-
-/// Filters katas by language
-fn filter_katas_by_language(all_katas: &[(String, String)], language: &str) -> Vec<String> {
-    all_katas
-        .iter()
-        .filter(|(lang, _)| lang == language)
-        .map(|(_, kata)| kata.clone())
-        .collect()
-}
-
-/// scans an external examples directory and returns a list of (language, kata_name) tuples
-fn scan_external_katas(examples_dir: &str) -> Vec<(String, String)> {
-    let mut katas = Vec::new();
-
-    let examples_path = Path::new(examples_dir);
-    if !examples_path.exists() {
-        eprintln!(
-            "Error: Examples directory '{}' does not exist",
-            examples_dir
-        );
-        std::process::exit(1);
-    }
-
-    let entries = match fs::read_dir(examples_path) {
-        Ok(entries) => entries,
-        Err(e) => {
-            eprintln!("Error: Failed to read examples directory: {}", e);
-            std::process::exit(1);
-        }
-    };
-
-    for entry in entries.filter_map(|e| e.ok()) {
-        let language_path = entry.path();
-        if !language_path.is_dir() {
-            continue;
-        }
-
-        let language = match entry.file_name().into_string() {
-            Ok(name) => name,
-            Err(_) => continue,
-        };
-
-        let kata_entries = match fs::read_dir(&language_path) {
-            Ok(entries) => entries,
-            Err(_) => continue,
-        };
-
-        for kata_entry in kata_entries.filter_map(|e| e.ok()) {
-            if !kata_entry.path().is_dir() {
-                continue;
-            }
-
-            if let Ok(kata_name) = kata_entry.file_name().into_string() {
-                katas.push((language.clone(), kata_name));
-            }
-        }
+/// returns the source of the given spec, or the one of the workspace
+fn template_source<'a>(
+    workspace: &'a Workspace,
+    spec: &Option<String>,
+    opened: &'a mut Option<Box<dyn KataSource>>,
+) -> &'a dyn KataSource {
+    match spec {
+        Some(spec) => &**opened.insert(open_source(spec)),
+        None => workspace.source.as_ref(),
     }
-
-    katas.sort_by(|a, b| {
-        // Sort by kata name first, then by language
-        match a.1.cmp(&b.1) {
-            std::cmp::Ordering::Equal => a.0.cmp(&b.0),
-            other => other,
-        }
-    });
-
-    katas
 }
 
-/// copies an embedded kata directory to the destination
-fn copy_embedded_kata(language: &str, kata_name: &str, dest: &Path) -> std::io::Result<()> {
-    // Get the embedded kata directory
-    let kata_path_str = format!("{}/{}", language, kata_name);
-    let kata_dir = EXAMPLE_KATAS.get_dir(&kata_path_str).ok_or_else(|| {
-        std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            format!("Embedded kata not found: {}", kata_path_str),
-        )
-    })?;
-
-    // Recursively copy all files from the embedded directory
-    fn copy_dir_recursive(dir: &include_dir::Dir, dest: &Path) -> std::io::Result<()> {
-        // Create destination directory
-        fs::create_dir_all(dest)?;
-
-        // Copy all files in this directory
-        for file in dir.files() {
-            let file_path = dest.join(file.path().file_name().unwrap());
-            fs::write(&file_path, file.contents())?;
-
-            // Set executable permissions for run.sh files
-            #[cfg(unix)]
-            if file_path.file_name().and_then(|n| n.to_str()) == Some("run.sh") {
-                use std::os::unix::prelude::PermissionsExt;
-                fs::set_permissions(&file_path, fs::Permissions::from_mode(0o755))?;
-            }
-        }
-
-        // Recursively copy subdirectories
-        for subdir in dir.dirs() {
-            let subdir_name = subdir.path().file_name().unwrap();
-            let subdir_dest = dest.join(subdir_name);
-            copy_dir_recursive(subdir, &subdir_dest)?;
-        }
-
-        Ok(())
-    }
-
-    copy_dir_recursive(kata_dir, dest)
+/// a template `katac init` copied to the katas folder, or left out because the kata was already
+/// there
+#[derive(Debug, Clone)]
+pub struct InitializedKata {
+    pub template: Template,
+    /// name of the kata folder: the name of the template, or `<language>_<kata>` when a template
+    /// of the same name was copied before
+    pub name: String,
+    pub path: PathBuf,
+    /// the kata was already in the katas folder, so nothing was copied
+    pub skipped: bool,
+    /// the line describing the kata, from its kata.toml
+    pub summary: Option<String>,
+    /// whether the Makefile of the embedded kata was added to the copy
+    pub created_makefile: bool,
 }
 
-/// initializes katas by selecting from example templates
-pub fn init_from_examples(args: &Args, examples_dir: &Option<String>, select: &Option<String>) {
-    let katas_path = katas_dir(args);
-
-    // Determine whether to use embedded or external katas
-    let use_embedded = examples_dir.is_none();
-
-    // Scan for available example katas
-    let available_katas = if use_embedded {
-        scan_embedded_katas()
-    } else {
-        scan_external_katas(examples_dir.as_ref().unwrap())
-    };
-
-    if available_katas.is_empty() {
-        if use_embedded {
-            eprintln!("Error: No embedded example katas found");
-        } else {
-            eprintln!(
-                "Error: No example katas found in '{}'",
-                examples_dir.as_ref().unwrap()
-            );
-        }
-        std::process::exit(1);
-    }
-
-    // Format options for display: "[language] KataName"
-    let options: Vec<String> = available_katas
-        .iter()
-        .map(|(lang, kata)| format!("[{}] {}", lang, kata))
-        .collect();
-
-    // Get selections (either from --select flag or interactive prompt)
-    let selections: Vec<usize> = if let Some(select_str) = select {
-        // Non-interactive mode for testing/automation
-        let selected_names: Vec<String> = select_str
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .collect();
-
-        options
-            .iter()
-            .enumerate()
-            .filter(|(_, opt)| selected_names.iter().any(|sel| opt.contains(sel)))
-            .map(|(i, _)| i)
-            .collect()
-    } else {
-        // Interactive mode - Two-step process
-
-        // Step 1: Select language
-        let languages = get_available_languages();
-        let selected_language = match Select::new("Choose a language", languages.clone()).prompt() {
-            Ok(lang) => lang,
-            Err(e) => {
-                // Exit silently if user cancelled the operation
-                if matches!(e, inquire::InquireError::OperationCanceled) {
-                    std::process::exit(0);
-                }
-                eprintln!("Error: Failed to read user input: {}", e);
-                std::process::exit(1);
-            }
-        };
-
-        // Step 2: Fuzzy multi-select katas for chosen language
-        let language_katas = filter_katas_by_language(&available_katas, &selected_language);
-
-        if language_katas.is_empty() {
-            eprintln!("Error: No katas found for language '{}'", selected_language);
-            std::process::exit(1);
-        }
-
-        let selected_kata_names = match MultiSelect::new(
-            format!(
-                "Select {} katas (type to filter, SPACE to select, ENTER to confirm)",
-                selected_language
-            )
-            .as_str(),
-            language_katas.clone(),
-        )
-        .prompt()
-        {
-            Ok(selections) => selections,
-            Err(e) => {
-                // Exit silently if user cancelled the operation
-                if matches!(e, inquire::InquireError::OperationCanceled) {
-                    std::process::exit(0);
-                }
-                eprintln!("Error: Failed to read user input: {}", e);
-                std::process::exit(1);
-            }
-        };
-
-        // Map selected kata names back to indices in available_katas
-        selected_kata_names
-            .into_iter()
-            .filter_map(|kata_name| {
-                available_katas
-                    .iter()
-                    .position(|(lang, name)| lang == &selected_language && name == &kata_name)
-            })
-            .collect()
-    };
-
-    if selections.is_empty() {
-        println!("No katas selected. Exiting.");
-        return;
-    }
-
-    // Create katas directory if it doesn't exist
+/// copies the given templates of a source to the katas folder. Returns the katas that were
+/// copied or skipped, or every template that couldn't be copied along with them
+fn init_katas(
+    workspace: &Workspace,
+    source: &dyn KataSource,
+    templates: &[Template],
+) -> Result<Vec<InitializedKata>, Error> {
+    let katas_path = &workspace.katas_dir;
     if !Path::new(&katas_path).exists() {
-        if let Err(e) = fs::create_dir_all(&katas_path) {
-            eprintln!("Error: Failed to create katas directory: {}", e);
-            std::process::exit(1);
-        }
+        fs::create_dir_all(katas_path).map_err(Error::io(format!(
+            "failed to create the katas directory {}",
+            katas_path
+        )))?;
     }
 
-    // Copy selected katas
+    let mut initialized = Vec::new();
     let mut errors = Vec::new();
-    let mut copied_count = 0;
     let mut seen_names: HashSet<String> = HashSet::new();
-
-    for &idx in &selections {
-        let (language, kata_name) = &available_katas[idx];
-        let language = language.as_str();
-        let kata_name = kata_name.as_str();
-
-        // Determine final destination name
-        let final_dest_name = if seen_names.contains(kata_name) {
-            format!("{}_{}", language, kata_name)
-        } else {
-            kata_name.to_string()
+    for template in templates {
+        let language = template.language.as_str();
+        let kata_name = template.kata.as_str();
+
+        // a second template of the same name is copied under another name
+        let name = match seen_names.contains(kata_name) {
+            true => format!("{}_{}", language, kata_name),
+            false => kata_name.to_string(),
+        };
+        let path = PathBuf::from(&katas_path).join(&name);
+        let mut kata = InitializedKata {
+            template: template.clone(),
+            name,
+            path,
+            skipped: false,
+            summary: None,
+            created_makefile: false,
         };
 
-        let final_dest = PathBuf::from(&katas_path).join(&final_dest_name);
-
-        // Skip if final destination already exists
-        if final_dest.exists() {
-            println!(
-                "Note: {} already exists, skipping [{}] {}",
-                final_dest_name, language, kata_name
-            );
+        if kata.path.exists() {
+            kata.skipped = true;
+            initialized.push(kata);
             continue;
         }
-
-        // Show note if we're renaming due to duplicate selection
-        if final_dest_name != *kata_name {
-            println!(
-                "Note: {} was already selected, creating [{}] {} as {}",
-                kata_name, language, kata_name, final_dest_name
-            );
-        }
-
-        // Copy from embedded or external source
-        let copy_result = if use_embedded {
-            copy_embedded_kata(language, kata_name, &final_dest)
-        } else {
-            let src = PathBuf::from(examples_dir.as_ref().unwrap())
-                .join(language)
-                .join(kata_name);
-
-            // For external, use the temp directory approach
-            let temp_name = format!(".tmp_{}_{}", language, kata_name);
-            let temp_path = PathBuf::from(&katas_path).join(&temp_name);
-
-            if let Err(e) = fs::create_dir_all(&temp_path) {
-                Err(std::io::Error::other(format!(
-                    "Failed to create temp directory: {}",
-                    e
-                )))
-            } else {
-                match fs_extra::copy_items(&[&src], &temp_path, &CopyOptions::new()) {
-                    Ok(_) => {
-                        let copied_dir = temp_path.join(kata_name);
-                        let result = fs::rename(&copied_dir, &final_dest);
-                        let _ = fs::remove_dir_all(&temp_path);
-                        result
-                    }
-                    Err(e) => {
-                        let _ = fs::remove_dir_all(&temp_path);
-                        Err(std::io::Error::other(e.to_string()))
-                    }
-                }
-            }
-        };
-
-        match copy_result {
+        match source.fetch(template, &kata.path) {
             Ok(_) => {
-                println!(
-                    "✓ Copied [{}] {} to {}/{}",
-                    language, kata_name, katas_path, final_dest_name
-                );
-
-                // Ensure Makefile exists
-                ensure_makefile_exists(&final_dest, language, kata_name);
-
+                kata.summary = kata_manifest(&kata.path).summary();
+                kata.created_makefile = ensure_makefile_exists(&kata.path, language, kata_name);
                 seen_names.insert(kata_name.to_string());
-                copied_count += 1;
+                initialized.push(kata);
             }
             Err(e) => {
-                eprintln!("Error copying [{}] {}: {}", language, kata_name, e);
-                errors.push(format!("[{}] {}", language, kata_name));
+                remove_path(&kata.path);
+                errors.push(e);
             }
         }
     }
 
-    println!("\nSuccessfully initialized {} kata(s)!", copied_count);
-
     if !errors.is_empty() {
-        eprintln!("\nFailed to copy {} kata(s)", errors.len());
-        std::process::exit(1);
+        return Err(Error::InitFailed {
+            initialized,
+            errors,
+        });
     }
+    Ok(initialized)
 }
 
-/// upgrades katac to the latest version from GitHub releases
-pub fn upgrade_katac(force: bool) {
+/// upgrades katac to the latest version from GitHub releases, reporting each step to `progress`
+pub fn upgrade_katac(force: bool, progress: &mut dyn FnMut(&str)) -> Result<(), Error> {
     const REPO: &str = "aldevv/katac";
     const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-    println!("Current version: {}", CURRENT_VERSION);
-    println!("Checking for updates...");
+    progress(&format!("Current version: {}", CURRENT_VERSION));
+    progress("Checking for updates...");
 
     // Get latest version from GitHub
-    let latest_version = match get_latest_github_version(REPO) {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("Error: Failed to check for updates: {}", e);
-            std::process::exit(1);
-        }
-    };
+    let latest_version = get_latest_github_version(REPO)
+        .map_err(|e| Error::Upgrade(format!("failed to check for updates: {}", e)))?;
 
-    println!("Latest version: {}", latest_version);
+    progress(&format!("Latest version: {}", latest_version));
 
     // Compare versions
     let current = CURRENT_VERSION.trim_start_matches('v');
     let latest = latest_version.trim_start_matches('v');
 
     if !force && current == latest {
-        println!("✓ Already on the latest version!");
-        return;
+        progress("✓ Already on the latest version!");
+        return Ok(());
     }
 
     if !force && is_version_newer(latest, current) == Some(false) {
-        println!(
+        progress(&format!(
             "✓ Your version ({}) is newer than or equal to the latest release ({})!",
             current, latest
-        );
-        return;
+        ));
+        return Ok(());
     }
 
-    println!("\nUpgrading from {} to {}...", current, latest);
-    println!("Downloading katac {}...", latest_version);
+    progress(&format!("\nUpgrading from {} to {}...", current, latest));
+    progress(&format!("Downloading katac {}...", latest_version));
 
     // Detect system
-    let (os, arch) = match detect_system() {
-        Ok(sys) => sys,
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            eprintln!(
-                "Please install manually from: https://github.com/{}/releases",
-                REPO
-            );
-            std::process::exit(1);
-        }
-    };
+    let (os, arch) = detect_system().map_err(|e| {
+        Error::Upgrade(format!(
+            "{}\nPlease install manually from: https://github.com/{}/releases",
+            e, REPO
+        ))
+    })?;
 
     let target = get_rust_target(&os, &arch);
     let ext = if os == "windows" { "zip" } else { "tar.gz" };
@@ -1057,33 +2834,31 @@ pub fn upgrade_katac(force: bool) {
     let temp_dir = match std::env::temp_dir().to_str() {
         Some(d) => PathBuf::from(d),
         None => {
-            eprintln!("Error: Could not access temp directory");
-            std::process::exit(1);
+            return Err(Error::Upgrade(
+                "could not access the temp directory".to_string(),
+            ))
         }
     };
 
     let download_path = temp_dir.join(&filename);
 
-    println!("Downloading from: {}", url);
-    if let Err(e) = download_file(&url, &download_path) {
-        eprintln!("Error: Failed to download: {}", e);
-        std::process::exit(1);
-    }
+    progress(&format!("Downloading from: {}", url));
+    download_file(&url, &download_path).map_err(Error::io("failed to download"))?;
 
     // Extract
-    println!("Extracting...");
+    progress("Extracting...");
     let extract_dir = temp_dir.join("katac_upgrade");
     let _ = fs::remove_dir_all(&extract_dir);
-    if let Err(e) = fs::create_dir_all(&extract_dir) {
-        eprintln!("Error: Failed to create extraction directory: {}", e);
-        std::process::exit(1);
-    }
-
-    if let Err(e) = extract_archive(&download_path, &extract_dir, ext) {
-        eprintln!("Error: Failed to extract: {}", e);
+    fs::create_dir_all(&extract_dir)
+        .map_err(Error::io("failed to create the extraction directory"))?;
+    // removes the extracted files before failing
+    let cleanup = |error: Error| {
         let _ = fs::remove_dir_all(&extract_dir);
-        std::process::exit(1);
-    }
+        error
+    };
+
+    extract_archive(&download_path, &extract_dir, ext)
+        .map_err(|e| cleanup(Error::io("failed to extract")(e)))?;
 
     // Find the binary
     let binary_name = if os == "windows" {
@@ -1094,40 +2869,29 @@ pub fn upgrade_katac(force: bool) {
     let new_binary = extract_dir.join(binary_name);
 
     if !new_binary.exists() {
-        eprintln!("Error: Binary not found in archive");
-        let _ = fs::remove_dir_all(&extract_dir);
-        std::process::exit(1);
+        return Err(cleanup(Error::Upgrade(
+            "binary not found in archive".to_string(),
+        )));
     }
 
     // Get current executable path
-    let current_exe = match std::env::current_exe() {
-        Ok(path) => path,
-        Err(e) => {
-            eprintln!("Error: Could not determine current executable path: {}", e);
-            let _ = fs::remove_dir_all(&extract_dir);
-            std::process::exit(1);
-        }
-    };
+    let current_exe = std::env::current_exe()
+        .map_err(|e| cleanup(Error::io("could not determine the current executable path")(e)))?;
 
     // Replace the binary
-    println!("Installing to {}...", current_exe.display());
+    progress(&format!("Installing to {}...", current_exe.display()));
 
     // On Windows, we can't replace a running exe, so we rename it first
     #[cfg(target_os = "windows")]
     {
         let backup = current_exe.with_extension("exe.old");
         let _ = fs::remove_file(&backup);
-        if let Err(e) = fs::rename(&current_exe, &backup) {
-            eprintln!("Error: Failed to backup current binary: {}", e);
-            let _ = fs::remove_dir_all(&extract_dir);
-            std::process::exit(1);
-        }
+        fs::rename(&current_exe, &backup)
+            .map_err(|e| cleanup(Error::io("failed to back up the current binary")(e)))?;
 
         if let Err(e) = fs::copy(&new_binary, &current_exe) {
-            eprintln!("Error: Failed to install new binary: {}", e);
             let _ = fs::rename(&backup, &current_exe);
-            let _ = fs::remove_dir_all(&extract_dir);
-            std::process::exit(1);
+            return Err(cleanup(Error::io("failed to install the new binary")(e)));
         }
 
         let _ = fs::remove_file(&backup);
@@ -1151,17 +2915,15 @@ pub fn upgrade_katac(force: bool) {
         let staged = match current_exe.parent() {
             Some(dir) => dir.join(format!(".katac-upgrade-{}.tmp", std::process::id())),
             None => {
-                eprintln!("Error: Could not determine install directory");
-                let _ = fs::remove_dir_all(&extract_dir);
-                std::process::exit(1);
+                return Err(cleanup(Error::Upgrade(
+                    "could not determine the install directory".to_string(),
+                )))
             }
         };
 
         if let Err(e) = fs::copy(&new_binary, &staged) {
-            eprintln!("Error: Failed to stage new binary: {}", e);
             let _ = fs::remove_file(&staged);
-            let _ = fs::remove_dir_all(&extract_dir);
-            std::process::exit(1);
+            return Err(cleanup(Error::io("failed to stage the new binary")(e)));
         }
 
         if let Ok(metadata) = fs::metadata(&staged) {
@@ -1171,10 +2933,8 @@ pub fn upgrade_katac(force: bool) {
         }
 
         if let Err(e) = fs::rename(&staged, &current_exe) {
-            eprintln!("Error: Failed to install new binary: {}", e);
             let _ = fs::remove_file(&staged);
-            let _ = fs::remove_dir_all(&extract_dir);
-            std::process::exit(1);
+            return Err(cleanup(Error::io("failed to install the new binary")(e)));
         }
     }
 
@@ -1182,8 +2942,9 @@ pub fn upgrade_katac(force: bool) {
     let _ = fs::remove_file(&download_path);
     let _ = fs::remove_dir_all(&extract_dir);
 
-    println!("✓ Successfully upgraded to version {}!", latest);
-    println!("Run 'katac --version' to verify");
+    progress(&format!("✓ Successfully upgraded to version {}!", latest));
+    progress("Run 'katac --version' to verify");
+    Ok(())
 }
 
 fn get_latest_github_version(repo: &str) -> Result<String, String> {
@@ -1289,7 +3050,7 @@ fn extract_archive(archive: &Path, dest: &Path, ext: &str) -> std::io::Result<()
         }
     } else {
         let output = Command::new("tar")
-            .args(["xzf"])
+            .args(["xf"])
             .arg(archive)
             .arg("-C")
             .arg(dest)
@@ -1319,6 +3080,7 @@ fn is_version_newer(v1: &str, v2: &str) -> Option<bool> {
 #[cfg(test)]
 mod upgrade_tests {
     use super::*;
+    use crate::test_util::TempDir;
 
     #[test]
     fn version_newer_patch_bump() {
@@ -1426,17 +3188,9 @@ mod upgrade_tests {
         );
     }
 
-    fn unique_tmp_dir(tag: &str) -> PathBuf {
-        let dir =
-            std::env::temp_dir().join(format!("katac_upgrade_test_{}_{}", tag, std::process::id()));
-        let _ = fs::remove_dir_all(&dir);
-        fs::create_dir_all(&dir).unwrap();
-        dir
-    }
-
     #[test]
     fn extract_archive_tar_gz_round_trip() {
-        let tmp = unique_tmp_dir("tar");
+        let tmp = TempDir::new("upgrade_tar");
 
         let src_dir = tmp.join("src");
         fs::create_dir_all(&src_dir).unwrap();
@@ -1460,20 +3214,196 @@ mod upgrade_tests {
         let extracted = dest.join("hello.txt");
         assert!(extracted.exists(), "extracted file missing");
         assert_eq!(fs::read_to_string(&extracted).unwrap(), "world");
-
-        let _ = fs::remove_dir_all(&tmp);
     }
 
     #[test]
     fn extract_archive_returns_err_on_missing_file() {
-        let tmp = unique_tmp_dir("missing");
+        let tmp = TempDir::new("upgrade_missing");
         let nonexistent = tmp.join("does-not-exist.tar.gz");
         let dest = tmp.join("dest");
         fs::create_dir_all(&dest).unwrap();
 
         let result = extract_archive(&nonexistent, &dest, "tar.gz");
         assert!(result.is_err(), "expected Err on missing archive");
+    }
+}
+
+#[cfg(test)]
+mod watch_tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn snapshot_detects_new_and_modified_files() {
+        let tmp = TempDir::new("watch_modified");
+        fs::write(tmp.join("main.go"), "package main").unwrap();
+        let before = snapshot_dir(&tmp);
+        assert_eq!(before.len(), 1);
+
+        fs::create_dir_all(tmp.join("pkg")).unwrap();
+        fs::write(tmp.join("pkg").join("lib.go"), "package pkg").unwrap();
+        let after = snapshot_dir(&tmp);
+        assert_eq!(after.len(), 2);
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn snapshot_ignores_build_artifact_dirs() {
+        let tmp = TempDir::new("watch_ignored");
+        fs::write(tmp.join("queue.py"), "").unwrap();
+        let before = snapshot_dir(&tmp);
+
+        fs::create_dir_all(tmp.join("__pycache__")).unwrap();
+        fs::write(tmp.join("__pycache__").join("queue.pyc"), "").unwrap();
+        assert_eq!(before, snapshot_dir(&tmp));
+    }
+
+    #[test]
+    fn snapshot_of_missing_dir_is_empty() {
+        assert!(snapshot_dir(Path::new("does/not/exist")).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod run_tests {
+    use super::*;
+
+    fn workspace(config: &str) -> Workspace {
+        Workspace {
+            katas_dir: KATAS_DIR.to_string(),
+            days: Days::new(DAYS_DIR, DayScheme::Numbered, Local::now().date_naive()),
+            config: toml::from_str(config).unwrap(),
+            layers: config::Layers::default(),
+            history: History::new(HISTORY_FILE),
+            runners: Runners::default(),
+            source: Box::new(EmbeddedSource),
+            source_fallback: false,
+        }
+    }
+
+    fn context<'a>(workspace: &'a Workspace, options: &'a RunOptions) -> RunContext<'a> {
+        RunContext {
+            workspace,
+            day: 1,
+            options,
+            command: None,
+        }
+    }
+
+    #[test]
+    fn parser_priority() {
+        let config = r#"
+[katas]
+parser = "auto"
+
+[kata.RingBuffer]
+parser = "go-test"
+"#;
+        let (configured, empty) = (workspace(config), workspace(""));
+        let options = RunOptions::default();
+        let ctx = context(&configured, &options);
+        assert_eq!(ctx.parser("RingBuffer"), ResultParser::GoTest);
+        assert_eq!(ctx.parser("LRU"), ResultParser::Auto);
+        assert_eq!(context(&empty, &options).parser("LRU"), ResultParser::Off);
+
+        let options = RunOptions {
+            parser: Some(ResultParser::Tap),
+            ..RunOptions::default()
+        };
+        assert_eq!(
+            context(&configured, &options).parser("RingBuffer"),
+            ResultParser::Tap
+        );
+    }
 
-        let _ = fs::remove_dir_all(&tmp);
+    #[test]
+    fn limits_default_to_none() {
+        let workspace = workspace("");
+        let options = RunOptions::default();
+        let ctx = context(&workspace, &options);
+        assert_eq!(ctx.limits("LRU"), KataLimits::default());
+    }
+
+    #[test]
+    fn kata_config_overrides_global_config() {
+        let workspace = workspace(
+            r#"
+[katas]
+timeout = 30
+max_memory_mb = 512
+
+[kata.RingBuffer]
+timeout = 5
+"#,
+        );
+        let options = RunOptions::default();
+        let ctx = context(&workspace, &options);
+
+        let limits = ctx.limits("RingBuffer");
+        assert_eq!(limits.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(limits.max_memory_mb, Some(512));
+
+        let limits = ctx.limits("LRU");
+        assert_eq!(limits.timeout, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn timeout_arg_overrides_config() {
+        let options = RunOptions {
+            timeout: Some(2),
+            ..RunOptions::default()
+        };
+        let workspace = workspace("[kata.RingBuffer]\ntimeout = 5\n");
+        let ctx = context(&workspace, &options);
+        assert_eq!(
+            ctx.limits("RingBuffer").timeout,
+            Some(Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn describe_command_quotes_arguments_with_spaces() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo hi there"]);
+        assert_eq!(describe_command(&cmd), "sh -c 'echo hi there'");
+
+        let mut cmd = Command::new("make");
+        cmd.args(["run", "-s"]);
+        assert_eq!(describe_command(&cmd), "make run -s");
+    }
+
+    #[test]
+    fn truncate_output_keeps_the_end() {
+        assert_eq!(truncate_output("short"), "short");
+
+        let long = format!("{}END", "x".repeat(RECORD_OUTPUT_LIMIT));
+        let truncated = truncate_output(&long);
+        assert!(truncated.starts_with("[... 3 bytes truncated]\n"));
+        assert!(truncated.ends_with("END"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn wait_with_timeout_kills_slow_child() {
+        let mut cmd = Command::new("sleep");
+        cmd.arg("10");
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        let mut child = cmd.spawn().unwrap();
+
+        let start = Instant::now();
+        let status = wait_with_timeout(&mut child, Some(Duration::from_millis(100))).unwrap();
+        assert_eq!(status, None);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn wait_with_timeout_returns_status_of_fast_child() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let status = wait_with_timeout(&mut child, Some(Duration::from_secs(5))).unwrap();
+        assert!(status.unwrap().success());
     }
 }
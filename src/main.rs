@@ -1,30 +1,938 @@
 use katac::{
-    copy_katas, init_from_examples, new_kata, random_katas, run_katas, upgrade_katac, Args,
-    Subcommands::Init, Subcommands::New, Subcommands::Random, Subcommands::Run, Subcommands::Start,
-    Subcommands::Upgrade,
+    format_duration, format_rate, upgrade_katac, Args, ColorChoice, ConfigAction, CopyRecord,
+    DayScheme, Error, HistoryEvent, KataDiff, ListFilter, Listing, OutputFormat, RandomOptions,
+    RandomPicks, ResetResult, RunEvent, RunOptions, RunRecord, RunResult, RunStatus, StartOptions,
+    StatsFilter, StatsRecord, StreakRecord, Subcommands::Config, Subcommands::Diff,
+    Subcommands::Init, Subcommands::List, Subcommands::MigrateDays, Subcommands::New,
+    Subcommands::Random, Subcommands::Reset, Subcommands::Run, Subcommands::Start,
+    Subcommands::Stats, Subcommands::Streak, Subcommands::Upgrade, TargetDay, Template,
+    TemplateRecord, Workspace, CONFIG_FILE_NAME, EMBEDDED_SOURCE,
 };
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use inquire::{MultiSelect, Select};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Duration;
 
 fn main() {
-    env_logger::init();
+    init_logger();
     let args = Args::parse();
 
+    if let Err(e) = run(&args) {
+        report(&e);
+        // cancelling a prompt isn't a failure
+        std::process::exit(match e {
+            Error::Cancelled => 0,
+            _ => 1,
+        });
+    }
+}
+
+/// shows the warnings of katac on stderr, and more with RUST_LOG
+fn init_logger() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+        .format(|buf, record| match record.level() {
+            log::Level::Warn => writeln!(buf, "Warning: {}", record.args()),
+            level => writeln!(buf, "[{} {}] {}", level, record.target(), record.args()),
+        })
+        .init();
+}
+
+/// prints why the command failed to stderr
+fn report(error: &Error) {
+    match error {
+        Error::Cancelled => {}
+        // the summary of the run already shows which katas failed
+        Error::RunFailed { .. } => {}
+        Error::CopyFailed { errors, .. }
+        | Error::InitFailed { errors, .. }
+        | Error::MigrateFailed { errors, .. } => {
+            for e in errors {
+                eprintln!("Error: {}", e);
+            }
+            eprintln!("\nError: {}", error);
+        }
+        _ => eprintln!("Error: {}", error),
+    }
+}
+
+/// runs the command given on the command line
+fn run(args: &Args) -> Result<(), Error> {
+    // upgrading doesn't need a workspace, nor a valid config file
+    if let Some(Upgrade { force }) = args.subcommand {
+        return upgrade_katac(force, &mut |step| println!("{}", step));
+    }
+    let workspace = Workspace::from_args(args)?;
+    let format = args.format;
+
     match args.subcommand {
-        None => copy_katas(&args, &args.kata_names),
+        None => print_copied(
+            &workspace,
+            workspace.start(&args.kata_names, &StartOptions::default()),
+            format,
+        ),
         Some(ref subcommand) => match subcommand {
             Run {
                 kata_names,
                 command,
-            } => run_katas(&args, kata_names, command),
-            Random { number_of_katas } => copy_katas(&args, &random_katas(&args, *number_of_katas)),
-            Start { kata_names } => copy_katas(&args, kata_names),
-            New { kata_name } => new_kata(&args, kata_name),
-            Init {
-                examples_dir,
-                select,
-            } => init_from_examples(&args, examples_dir, select),
-            Upgrade { force } => upgrade_katac(*force),
+                shell,
+                watch,
+                jobs,
+                timeout,
+                parser,
+                junit,
+            } => run_katas(
+                &workspace,
+                kata_names,
+                &RunOptions {
+                    command: command.clone(),
+                    shell: *shell,
+                    watch: *watch,
+                    jobs: *jobs as usize,
+                    timeout: *timeout,
+                    parser: *parser,
+                    junit: junit.clone(),
+                    // keep stdout clean for machine readable formats
+                    capture_output: format != OutputFormat::Text,
+                },
+                format,
+            ),
+            Random {
+                number_of_katas,
+                strategy,
+                seed,
+                seed_from_date,
+                pool,
+            } => {
+                let picks = workspace.random(
+                    *number_of_katas,
+                    &RandomOptions {
+                        strategy: *strategy,
+                        seed: *seed,
+                        seed_from_date: *seed_from_date,
+                        pool: pool.clone(),
+                    },
+                )?;
+                print_picks(&picks, format);
+                print_copied(
+                    &workspace,
+                    workspace.copy_katas(
+                        &picks.katas,
+                        HistoryEvent::Random,
+                        &StartOptions::default(),
+                    ),
+                    format,
+                )
+            }
+            Start {
+                kata_names,
+                today,
+                day,
+                new_day,
+                force,
+            } => {
+                let day = match (today, day, new_day) {
+                    (true, _, _) => Some(TargetDay::Current),
+                    (_, Some(day), _) => Some(TargetDay::Day(day.clone())),
+                    (_, _, true) => Some(TargetDay::Next),
+                    _ => None,
+                };
+                print_copied(
+                    &workspace,
+                    workspace.start(kata_names, &StartOptions { day, force: *force }),
+                    format,
+                )
+            }
+            New { kata_name } => {
+                let path = workspace.new_kata(kata_name)?;
+                let location = path.parent().and_then(|p| p.to_str()).unwrap_or(".");
+                println!("{} created in {}.", kata_name, location);
+                Ok(())
+            }
+            Init { source, select } => init(&workspace, source, select),
+            Config { action } => match action {
+                ConfigAction::Show { origin } => {
+                    show_config(&workspace, *origin, format);
+                    Ok(())
+                }
+            },
+            Stats {
+                katas,
+                since,
+                language,
+            } => {
+                let stats = workspace.stats(&StatsFilter {
+                    katas: katas.clone(),
+                    since: *since,
+                    language: language.clone(),
+                })?;
+                print_stats(&workspace, &stats, format);
+                Ok(())
+            }
+            Streak { weeks } => {
+                print_streak(&workspace.streak(*weeks)?, format);
+                Ok(())
+            }
+            List {
+                kind,
+                tag,
+                language,
+                source,
+            } => {
+                let filter = ListFilter {
+                    tag: tag.clone(),
+                    language: language.clone(),
+                };
+                let listing = workspace.list(*kind, &filter, source)?;
+                print_listing(&workspace, &listing, format);
+                Ok(())
+            }
+            Reset { kata_name, files } => {
+                print_reset(&workspace.reset(kata_name, files)?);
+                Ok(())
+            }
+            Diff {
+                kata_name,
+                from,
+                to,
+                color,
+            } => {
+                print_diff(&workspace.diff(kata_name, from, to)?, *color);
+                Ok(())
+            }
+            MigrateDays { to, dry_run } => migrate_days(&workspace, *to, *dry_run),
+            Upgrade { .. } => unreachable!("upgrade is handled before the workspace is opened"),
+        },
+    }
+}
+
+/// prints a record as a single line of json
+fn print_ndjson<T: Serialize>(record: &T) {
+    match serde_json::to_string(record) {
+        Ok(line) => println!("{}", line),
+        Err(e) => eprintln!("Error: failed to serialize record: {}", e),
+    }
+}
+
+/// prints records, or a single object, as pretty json
+fn print_json<T: Serialize + ?Sized>(records: &T) {
+    match serde_json::to_string_pretty(records) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Error: failed to serialize records: {}", e),
+    }
+}
+
+/// prints the records as json or ndjson, or calls `print_text` with them
+fn print_list<T: Serialize>(format: OutputFormat, records: &[T], print_text: impl FnOnce(&[T])) {
+    match format {
+        OutputFormat::Text => print_text(records),
+        OutputFormat::Json => print_json(records),
+        OutputFormat::Ndjson => records.iter().for_each(print_ndjson),
+    }
+}
+
+/// prints a single record as json or ndjson, or calls `print_text` with it
+fn print_record<T: Serialize>(format: OutputFormat, record: &T, print_text: impl FnOnce(&T)) {
+    match format {
+        OutputFormat::Text => print_text(record),
+        OutputFormat::Json => print_json(record),
+        OutputFormat::Ndjson => print_ndjson(record),
+    }
+}
+
+/// prints a table with a header line, each column as wide as its widest cell
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(headers[i].chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let print_row = |cells: Vec<&str>| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+
+    print_row(headers.to_vec());
+    for row in rows {
+        print_row(row.iter().map(|c| c.as_str()).collect());
+    }
+}
+
+/// prints the katas that were copied to a day, also when others couldn't be, and opens them with
+/// the editor of the config
+fn print_copied(
+    workspace: &Workspace,
+    result: Result<Vec<CopyRecord>, Error>,
+    format: OutputFormat,
+) -> Result<(), Error> {
+    if let Ok(copied) | Err(Error::CopyFailed { copied, .. }) = &result {
+        print_list(format, copied, |copied| {
+            for kata in copied {
+                match kata.from_source {
+                    true => println!(
+                        "Copying {} from {} to {}...",
+                        kata.kata, kata.source, kata.day_name
+                    ),
+                    false => println!("Copying {} to {}...", kata.kata, kata.day_name),
+                }
+                if let Some(summary) = &kata.summary {
+                    println!("  {}", summary);
+                }
+                if kata.created_makefile {
+                    println!("  → Created Makefile for {}", kata.kata);
+                }
+            }
+        });
+        if format == OutputFormat::Text && !copied.is_empty() {
+            let paths: Vec<PathBuf> = copied
+                .iter()
+                .map(|k| PathBuf::from(&k.destination))
+                .collect();
+            if let Err(e) = workspace.open_in_editor(&paths) {
+                log::warn!("{}", e);
+            }
+        }
+    }
+    result.map(|_| ())
+}
+
+/// prints the seed of the picks and why each kata was picked, to stderr with machine readable
+/// formats
+fn print_picks(picks: &RandomPicks, format: OutputFormat) {
+    let mut msg = format!(
+        "Seed: {} (pass --seed {} to pick the same katas)",
+        picks.seed, picks.seed
+    );
+    if !picks.schedule.is_empty() {
+        let width = picks
+            .schedule
+            .iter()
+            .map(|p| p.kata.chars().count())
+            .max()
+            .unwrap_or(0);
+        msg.push_str("\nPicked by the spaced repetition schedule:\n");
+        for pick in &picks.schedule {
+            msg.push_str(&format!("  {:<width$}  {}\n", pick.kata, pick.reason));
+        }
+    }
+
+    if format == OutputFormat::Text {
+        println!("{}", msg);
+    } else {
+        eprintln!("{}", msg);
+    }
+}
+
+/// runs the katas, printing their output and a summary, or their records with machine readable
+/// formats
+fn run_katas(
+    workspace: &Workspace,
+    kata_names: &Option<Vec<String>>,
+    options: &RunOptions,
+    format: OutputFormat,
+) -> Result<(), Error> {
+    #[cfg(unix)]
+    handle_stop_signals();
+    let text = format == OutputFormat::Text;
+    let mut stdout = std::io::stdout();
+    let mut on_event = |event: RunEvent| match event {
+        RunEvent::Output(output) => {
+            if text {
+                let _ = stdout.write_all(output);
+                let _ = stdout.flush();
+            }
+        }
+        RunEvent::Finished(result) => {
+            if format == OutputFormat::Ndjson {
+                print_ndjson(&RunRecord::new(result));
+            }
+        }
+        RunEvent::BatchFinished(results) => match format {
+            OutputFormat::Text => print_run_summary(results),
+            OutputFormat::Json => {
+                let records: Vec<RunRecord> = results.iter().map(RunRecord::new).collect();
+                print_json(&records);
+            }
+            // records were already printed as each kata finished
+            OutputFormat::Ndjson => {}
         },
+        RunEvent::Watching { katas } => {
+            let msg = format!(
+                "Watching {} kata(s) for changes, press Ctrl-C to stop",
+                katas
+            );
+            if text {
+                println!("\n{}", msg);
+            } else {
+                eprintln!("{}", msg);
+            }
+        }
+        RunEvent::Changed(_) => {
+            if text {
+                clear_screen();
+            }
+        }
+    };
+    workspace.run(kata_names, options, &mut on_event)?;
+    Ok(())
+}
+
+/// kills the katas that run in their own process group before katac exits on Ctrl-C or SIGTERM,
+/// otherwise they would keep running in the background
+#[cfg(unix)]
+fn handle_stop_signals() {
+    extern "C" fn stop(signal: libc::c_int) {
+        katac::kill_running_katas();
+        // SAFETY: _exit is async-signal-safe
+        unsafe { libc::_exit(128 + signal) }
+    }
+
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        // SAFETY: the handler only calls async-signal-safe functions, and the action is
+        // initialized by sigemptyset before use
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = stop as *const () as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(signal, &action, std::ptr::null_mut()) != 0 {
+                log::warn!(
+                    "failed to handle signal {}: {}",
+                    signal,
+                    std::io::Error::last_os_error()
+                );
+            }
+        }
+    }
+}
+
+/// clears the terminal and moves the cursor to the top left corner
+fn clear_screen() {
+    print!("\x1B[2J\x1B[1;1H");
+    let _ = std::io::stdout().flush();
+}
+
+/// prints a table with the result and duration of every kata that was run
+fn print_run_summary(results: &[RunResult]) {
+    if results.is_empty() {
+        return;
+    }
+
+    let name_width = results
+        .iter()
+        .map(|r| r.kata_name.chars().count())
+        .chain(std::iter::once("kata".len()))
+        .max()
+        .unwrap_or(0);
+    let statuses: Vec<String> = results.iter().map(|r| r.status.to_string()).collect();
+    let status_width = statuses
+        .iter()
+        .map(|s| s.chars().count())
+        .chain(std::iter::once("result".len()))
+        .max()
+        .unwrap_or(0);
+
+    // the tests column is only shown when the tests of some kata were found
+    let tests: Option<Vec<String>> = results.iter().any(|r| r.tests.is_some()).then(|| {
+        results
+            .iter()
+            .map(|r| match r.tests_passed() {
+                Some((passed, total)) => format!("{}/{}", passed, total),
+                None => "-".to_string(),
+            })
+            .collect()
+    });
+    let tests_width = tests
+        .iter()
+        .flatten()
+        .map(|t| t.chars().count())
+        .chain(std::iter::once("tests".len()))
+        .max()
+        .unwrap_or(0);
+
+    println!("\nSummary");
+    println!("-------");
+    match tests {
+        Some(_) => println!(
+            "{:<name_width$}  {:<status_width$}  {:<tests_width$}  duration",
+            "kata", "result", "tests"
+        ),
+        None => println!(
+            "{:<name_width$}  {:<status_width$}  duration",
+            "kata", "result"
+        ),
+    }
+    for (i, (result, status)) in results.iter().zip(&statuses).enumerate() {
+        let duration = match result.status {
+            RunStatus::Skipped => "-".to_string(),
+            _ => format!("{:.2}s", result.duration.as_secs_f64()),
+        };
+        match &tests {
+            Some(tests) => println!(
+                "{:<name_width$}  {:<status_width$}  {:<tests_width$}  {}",
+                result.kata_name, status, tests[i], duration
+            ),
+            None => println!(
+                "{:<name_width$}  {:<status_width$}  {}",
+                result.kata_name, status, duration
+            ),
+        }
+    }
+
+    let count = |f: fn(&RunStatus) -> bool| results.iter().filter(|r| f(&r.status)).count();
+    let timed_out = count(|s| *s == RunStatus::TimedOut);
+    println!(
+        "\n{} passed, {} failed, {} skipped{}",
+        count(|s| *s == RunStatus::Passed),
+        count(|s| matches!(s, RunStatus::Failed(_))),
+        count(|s| *s == RunStatus::Skipped),
+        if timed_out > 0 {
+            format!(", {} timed out", timed_out)
+        } else {
+            String::new()
+        }
+    );
+}
+
+/// prints where the attempt was backed up and what was reset
+fn print_reset(reset: &ResetResult) {
+    println!("Backed up your attempt to {}", reset.backup.display());
+    match reset.files.is_empty() {
+        true => println!("Reset {} in {}", reset.kata, reset.day_name),
+        false => println!(
+            "Reset {} of {} in {}",
+            reset
+                .files
+                .iter()
+                .map(|f| f.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            reset.kata,
+            reset.day_name
+        ),
+    }
+}
+
+/// prints the diff of a kata, in color if asked for or when stdout is a terminal
+fn print_diff(diff: &KataDiff, color: ColorChoice) {
+    let colored = match color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+        }
+    };
+    match (diff.diff.is_empty(), colored) {
+        (true, _) => println!("No changes in {}", diff.kata),
+        (false, true) => print!("{}", diff.colorized()),
+        (false, false) => print!("{}", diff.diff),
+    }
+}
+
+/// prints the stats as tables, one for the katas, the days and the languages
+fn print_stats(workspace: &Workspace, record: &StatsRecord, format: OutputFormat) {
+    print_record(format, record, |record| {
+        let stats = &record.stats;
+        if record.source == "days" {
+            println!("No history yet, counting the katas in the days folder\n");
+        }
+        if stats.katas.is_empty() {
+            println!("No practice found");
+            return;
+        }
+
+        let time = |d: Option<Duration>| d.map(format_duration).unwrap_or("-".to_string());
+        let or_dash = |s: Option<String>| s.unwrap_or("-".to_string());
+
+        println!("Katas");
+        println!("-----");
+        let rows: Vec<Vec<String>> = stats
+            .katas
+            .iter()
+            .map(|k| {
+                vec![
+                    k.kata.clone(),
+                    or_dash(k.language.clone()),
+                    k.practiced.to_string(),
+                    k.last_practiced.to_string(),
+                    format_rate(k.pass_rate),
+                    time(k.best),
+                    time(k.median),
+                    time(k.latest),
+                    or_dash(k.trend.map(|t| t.to_string())),
+                ]
+            })
+            .collect();
+        print_table(
+            &[
+                "kata",
+                "language",
+                "practiced",
+                "last",
+                "pass rate",
+                "best",
+                "median",
+                "latest",
+                "trend",
+            ],
+            &rows,
+        );
+
+        println!("\nDays");
+        println!("----");
+        let rows: Vec<Vec<String>> = stats
+            .days
+            .iter()
+            .map(|d| {
+                vec![
+                    workspace.days().name(d.day),
+                    d.date.to_string(),
+                    d.katas.to_string(),
+                    d.runs.to_string(),
+                    d.passed.to_string(),
+                ]
+            })
+            .collect();
+        print_table(&["day", "date", "katas", "runs", "passed"], &rows);
+
+        if stats.languages.is_empty() {
+            return;
+        }
+        println!("\nLanguages");
+        println!("---------");
+        let rows: Vec<Vec<String>> = stats
+            .languages
+            .iter()
+            .map(|l| {
+                vec![
+                    l.language.clone(),
+                    l.practiced.to_string(),
+                    l.runs.to_string(),
+                    format_rate(l.pass_rate),
+                ]
+            })
+            .collect();
+        print_table(&["language", "practiced", "runs", "pass rate"], &rows);
+    });
+}
+
+/// prints the current and longest streaks and the heatmap
+fn print_streak(record: &StreakRecord, format: OutputFormat) {
+    print_record(format, record, |record| {
+        if record.source == "days" {
+            println!("No history yet, counting the katas in the days folder\n");
+        }
+        match record.current_streak {
+            Some(s) => println!("Current streak: {} day(s), since {}", s.days, s.start),
+            None => println!("Current streak: 0 days"),
+        }
+        match record.longest_streak {
+            Some(s) => println!(
+                "Longest streak: {} day(s), {} to {}",
+                s.days, s.start, s.end
+            ),
+            None => println!("Longest streak: 0 days"),
+        }
+        print!("\n{}", record.heatmap);
+    });
+}
+
+/// prints the katas, the days or the templates listed
+fn print_listing(workspace: &Workspace, listing: &Listing, format: OutputFormat) {
+    match listing {
+        Listing::Katas(records) => print_list(format, records, |records| {
+            if records.is_empty() {
+                println!("No katas found in {}", workspace.katas_dir().display());
+                return;
+            }
+            let rows: Vec<Vec<String>> = records
+                .iter()
+                .map(|k| {
+                    vec![
+                        k.kata.clone(),
+                        k.language.clone().unwrap_or_default(),
+                        k.difficulty.map(|d| d.to_string()).unwrap_or_default(),
+                        k.tags.join(","),
+                        k.last_practiced
+                            .map(|d| d.to_string())
+                            .unwrap_or("never".to_string()),
+                        k.description.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            print_table(
+                &[
+                    "kata",
+                    "language",
+                    "difficulty",
+                    "tags",
+                    "last practiced",
+                    "description",
+                ],
+                &rows,
+            );
+        }),
+        Listing::Days(records) => print_list(format, records, |records| {
+            if records.is_empty() {
+                println!("No days found in {}", workspace.days().dir().display());
+                return;
+            }
+            let mut rows = Vec::new();
+            for day in records {
+                let day_name = day.name.clone();
+                if day.katas.is_empty() {
+                    rows.push(vec![
+                        day_name.clone(),
+                        String::new(),
+                        String::new(),
+                        String::new(),
+                    ]);
+                }
+                for (i, kata) in day.katas.iter().enumerate() {
+                    rows.push(vec![
+                        if i == 0 {
+                            day_name.clone()
+                        } else {
+                            String::new()
+                        },
+                        kata.kata.clone(),
+                        kata.language.clone().unwrap_or_default(),
+                        kata.status.unwrap_or("not run").to_string(),
+                    ]);
+                }
+            }
+            print_table(&["day", "kata", "language", "last run"], &rows);
+        }),
+        Listing::Templates(records) => print_list(format, records, |records| {
+            if records.is_empty() {
+                println!("No templates found");
+                return;
+            }
+            let width = records.iter().map(|t| t.kata.chars().count()).max();
+            // the templates are sorted by kata, they are shown by language
+            let mut languages: BTreeMap<&str, Vec<&TemplateRecord>> = BTreeMap::new();
+            for template in records {
+                languages
+                    .entry(&template.language)
+                    .or_default()
+                    .push(template);
+            }
+            for (i, (language, templates)) in languages.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("{} ({})", language, templates.len());
+                for template in templates {
+                    let line = format!(
+                        "  {:<width$}  {}",
+                        template.kata,
+                        template.description.as_deref().unwrap_or_default(),
+                        width = width.unwrap_or(0)
+                    );
+                    println!("{}", line.trim_end());
+                }
+            }
+        }),
+    }
+}
+
+/// a config key printed by `katac config show`
+#[derive(Serialize, Debug)]
+struct ConfigRecord {
+    key: String,
+    value: toml::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    origin: Option<String>,
+}
+
+/// prints the effective value of every config key, and where it comes from if `origin` is set
+fn show_config(workspace: &Workspace, origin: bool, format: OutputFormat) {
+    let records: Vec<ConfigRecord> = workspace
+        .config_entries()
+        .into_iter()
+        .map(|(key, value, from)| ConfigRecord {
+            key: key.to_string(),
+            value: value.clone(),
+            origin: origin.then(|| from.to_string()),
+        })
+        .collect();
+    print_list(format, &records, |records| {
+        if !origin {
+            for record in records {
+                println!("{} = {}", record.key, record.value);
+            }
+            return;
+        }
+        let rows: Vec<Vec<String>> = records
+            .iter()
+            .map(|r| {
+                let origin = r.origin.clone().unwrap_or_default();
+                vec![r.key.clone(), r.value.to_string(), origin]
+            })
+            .collect();
+        print_table(&["key", "value", "origin"], &rows);
+    });
+}
+
+/// renames the dayN folders, printing each of them with its new name
+fn migrate_days(workspace: &Workspace, to: Option<DayScheme>, dry_run: bool) -> Result<(), Error> {
+    let result = workspace.migrate_days(to, dry_run);
+    if let Ok(moved) | Err(Error::MigrateFailed { moved, .. }) = &result {
+        for day in moved {
+            println!("{} -> {}", day.from, day.to);
+        }
+    }
+    let moved = result?;
+    let days = workspace.days();
+    if moved.is_empty() {
+        println!("No dayN folders to migrate in {}", days.dir().display());
+        return Ok(());
+    }
+
+    let scheme = to.unwrap_or(days.scheme());
+    if !dry_run && days.scheme() != scheme {
+        println!(
+            "\nSet day_scheme = \"{}\" under [katas] in {} to keep using it",
+            scheme.to_possible_value().unwrap().get_name(),
+            CONFIG_FILE_NAME
+        );
+    }
+    Ok(())
+}
+
+/// copies the templates picked with --select, or interactively, from the given source or the
+/// one of the workspace to the katas folder
+fn init(
+    workspace: &Workspace,
+    source: &Option<String>,
+    select: &Option<String>,
+) -> Result<(), Error> {
+    let mut opened = None;
+    let source = workspace.template_source(source, &mut opened);
+
+    let templates = source.templates()?;
+    if templates.is_empty() {
+        return Err(Error::InvalidInput(match source.name().as_str() {
+            EMBEDDED_SOURCE => "no embedded example katas found".to_string(),
+            name => format!("no example katas found in '{}'", name),
+        }));
+    }
+    let selected = match select {
+        // non-interactive mode for testing/automation
+        Some(select) => select_templates(&templates, select),
+        None => prompt_templates(&templates, workspace.language())?,
+    };
+    if selected.is_empty() {
+        println!("No katas selected. Exiting.");
+        return Ok(());
+    }
+
+    let result = workspace.init(source, &selected);
+    if let Ok(initialized) | Err(Error::InitFailed { initialized, .. }) = &result {
+        let katas_dir = workspace.katas_dir().display();
+        for kata in initialized {
+            let (language, name) = (&kata.template.language, &kata.template.kata);
+            if kata.skipped {
+                println!(
+                    "Note: {} already exists, skipping [{}] {}",
+                    kata.name, language, name
+                );
+                continue;
+            }
+            if kata.name != *name {
+                println!(
+                    "Note: {} was already selected, creating [{}] {} as {}",
+                    name, language, name, kata.name
+                );
+            }
+            println!(
+                "✓ Copied [{}] {} to {}/{}",
+                language, name, katas_dir, kata.name
+            );
+            if let Some(summary) = &kata.summary {
+                println!("  {}", summary);
+            }
+            if kata.created_makefile {
+                println!("  → Created Makefile for {}", name);
+            }
+        }
+        let copied = initialized.iter().filter(|k| !k.skipped).count();
+        println!("\nSuccessfully initialized {} kata(s)!", copied);
+    }
+    result.map(|_| ())
+}
+
+/// returns the templates whose `[language] Kata` contains one of the comma separated names
+fn select_templates(templates: &[Template], select: &str) -> Vec<Template> {
+    let names: Vec<&str> = select.split(',').map(|s| s.trim()).collect();
+    templates
+        .iter()
+        .filter(|t| {
+            let option = format!("[{}] {}", t.language, t.kata);
+            names.iter().any(|name| option.contains(name))
+        })
+        .cloned()
+        .collect()
+}
+
+/// asks for a language, preselecting the given one, then for the templates of that language to
+/// copy
+fn prompt_templates(
+    templates: &[Template],
+    preselected: Option<&str>,
+) -> Result<Vec<Template>, Error> {
+    let mut languages: Vec<String> = templates.iter().map(|t| t.language.clone()).collect();
+    languages.sort();
+    languages.dedup();
+    let starting_cursor = preselected
+        .and_then(|language| languages.iter().position(|l| l == language))
+        .unwrap_or(0);
+    let language = Select::new("Choose a language", languages)
+        .with_starting_cursor(starting_cursor)
+        .prompt()
+        .map_err(prompt_error)?;
+
+    let language_templates: Vec<&Template> = templates
+        .iter()
+        .filter(|t| t.language == language)
+        .collect();
+    if language_templates.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "no katas found for language '{}'",
+            language
+        )));
+    }
+    let names = MultiSelect::new(
+        &format!(
+            "Select {} katas (type to filter, SPACE to select, ENTER to confirm)",
+            language
+        ),
+        language_templates.iter().map(|t| t.kata.clone()).collect(),
+    )
+    .prompt()
+    .map_err(prompt_error)?;
+
+    Ok(language_templates
+        .into_iter()
+        .filter(|t| names.contains(&t.kata))
+        .cloned()
+        .collect())
+}
+
+/// converts the error of an interactive prompt, pressing Esc cancels it
+fn prompt_error(e: inquire::InquireError) -> Error {
+    match e {
+        inquire::InquireError::OperationCanceled => Error::Cancelled,
+        e => Error::InvalidInput(format!("failed to read user input: {}", e)),
     }
 }
@@ -0,0 +1,162 @@
+//! The optional `kata.toml` of a kata folder: what the kata is about, how to run it and what
+//! not to copy to the days.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+/// name of the manifest in a kata folder
+pub const MANIFEST_FILE: &str = "kata.toml";
+
+/// how hard a kata is
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// the `kata.toml` of a kata, every field is optional
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct KataManifest {
+    pub description: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// language of the kata, detected from its files if None
+    pub language: Option<String>,
+    pub estimated_minutes: Option<u32>,
+    /// command that runs the kata, used instead of its Makefile or run.sh
+    pub run: Option<String>,
+    /// files restored when the kata is reset, relative to the kata folder. Every file if empty
+    #[serde(default)]
+    pub reset: Vec<PathBuf>,
+    /// file or folder with the solution, relative to the kata folder. It is never copied to
+    /// the days
+    pub solution: Option<PathBuf>,
+}
+
+impl KataManifest {
+    /// reads the manifest of the kata in the given folder, None if it has none
+    pub fn read(kata_path: &Path) -> Result<Option<KataManifest>, String> {
+        let path = kata_path.join(MANIFEST_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => KataManifest::parse(&content)
+                .map(Some)
+                .map_err(|e| format!("invalid {}: {}", path.display(), e)),
+            Err(e) if e.kind() == ErrorKind::NotFound || e.kind() == ErrorKind::NotADirectory => {
+                Ok(None)
+            }
+            Err(e) => Err(format!("failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    /// parses the content of a manifest
+    pub fn parse(content: &str) -> Result<KataManifest, String> {
+        let manifest: KataManifest =
+            toml::from_str(content).map_err(|e| e.message().to_string())?;
+        for path in manifest.solution.iter().chain(&manifest.reset) {
+            if !is_inside_kata(path) {
+                return Err(format!(
+                    "'{}' is not a path inside the kata folder",
+                    path.display()
+                ));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// returns a line describing the kata, e.g. `An LRU cache (medium, ~20 min)`, None if the
+    /// manifest says nothing about it
+    pub fn summary(&self) -> Option<String> {
+        let mut details = Vec::new();
+        if let Some(difficulty) = self.difficulty {
+            details.push(difficulty.to_string());
+        }
+        if let Some(minutes) = self.estimated_minutes {
+            details.push(format!("~{} min", minutes));
+        }
+        let details = (!details.is_empty()).then(|| format!("({})", details.join(", ")));
+
+        let summary: Vec<String> = self.description.iter().cloned().chain(details).collect();
+        (!summary.is_empty()).then(|| summary.join(" "))
+    }
+}
+
+/// returns whether the path is relative and stays inside the folder it is relative to
+pub(crate) fn is_inside_kata(path: &Path) -> bool {
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        && path.components().any(|c| matches!(c, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn full_manifest() {
+        let manifest = KataManifest::parse(
+            r#"
+description = "An LRU cache with O(1) get and put"
+difficulty = "medium"
+tags = ["cache", "linked-list"]
+language = "go"
+estimated_minutes = 20
+run = "go test ./..."
+reset = ["lru.go"]
+solution = "solution"
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.difficulty, Some(Difficulty::Medium));
+        assert_eq!(manifest.tags, ["cache", "linked-list"]);
+        assert_eq!(manifest.run.as_deref(), Some("go test ./..."));
+        assert_eq!(manifest.reset, [PathBuf::from("lru.go")]);
+        assert_eq!(manifest.solution, Some(PathBuf::from("solution")));
+        assert_eq!(
+            manifest.summary().as_deref(),
+            Some("An LRU cache with O(1) get and put (medium, ~20 min)")
+        );
+    }
+
+    #[test]
+    fn empty_manifest() {
+        let manifest = KataManifest::parse("").unwrap();
+        assert_eq!(manifest, KataManifest::default());
+        assert_eq!(manifest.summary(), None);
+    }
+
+    #[test]
+    fn invalid_manifests() {
+        assert!(KataManifest::parse("difficulty = \"impossible\"").is_err());
+        assert!(KataManifest::parse("runs = \"make\"").is_err());
+        assert_eq!(
+            KataManifest::parse("solution = \"../LRU\"").unwrap_err(),
+            "'../LRU' is not a path inside the kata folder"
+        );
+        assert!(KataManifest::parse("reset = [\"/etc/passwd\"]").is_err());
+        assert!(KataManifest::parse("solution = \".\"").is_err());
+    }
+
+    #[test]
+    fn missing_manifest() {
+        let dir = TempDir::new("manifest");
+        assert_eq!(KataManifest::read(&dir), Ok(None));
+    }
+}
@@ -0,0 +1,389 @@
+//! Parsers that turn the output of test frameworks into per test results, so `katac run` can
+//! report how many tests of a kata passed and which ones failed.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// outcome of a single test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// result of a single test found in the output of a kata
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub outcome: TestOutcome,
+    /// time the test took, when the framework reports it
+    pub duration: Option<Duration>,
+}
+
+impl TestCase {
+    fn new(name: &str, outcome: TestOutcome) -> Self {
+        TestCase {
+            name: name.to_string(),
+            outcome,
+            duration: None,
+        }
+    }
+}
+
+/// format of the output of a kata, used to find its test results
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResultParser {
+    /// pick the parser from the files of the kata
+    Auto,
+    /// don't parse the output
+    Off,
+    /// `go test -v`
+    GoTest,
+    /// `go test -json`
+    GoJson,
+    /// `python -m unittest -v`
+    Unittest,
+    /// `pytest -v`
+    Pytest,
+    /// Test Anything Protocol, e.g. `node --test`
+    Tap,
+}
+
+impl ResultParser {
+    /// parses the output of the kata in the given folder, returns None if no tests were found
+    pub fn parse(self, output: &str, kata_path: &Path) -> Option<Vec<TestCase>> {
+        let tests = match self {
+            ResultParser::Off => return None,
+            ResultParser::Auto => {
+                return auto_candidates(kata_path)
+                    .iter()
+                    .find_map(|parser| parser.parse(output, kata_path))
+            }
+            ResultParser::GoTest => parse_go_test(output),
+            ResultParser::GoJson => parse_go_json(output),
+            ResultParser::Unittest => parse_unittest(output),
+            ResultParser::Pytest => parse_pytest(output),
+            ResultParser::Tap => parse_tap(output),
+        };
+        if tests.is_empty() {
+            return None;
+        }
+        Some(tests)
+    }
+}
+
+/// returns the parsers worth trying for a kata, based on the language of its files
+fn auto_candidates(kata_path: &Path) -> &'static [ResultParser] {
+    use ResultParser::*;
+
+    match crate::kata_language(kata_path).as_deref() {
+        Some("go") => &[GoJson, GoTest],
+        Some("python") => &[Pytest, Unittest],
+        _ => &[GoJson, GoTest, Pytest, Unittest, Tap],
+    }
+}
+
+/// parses `--- PASS: TestName (0.01s)` lines, subtests included
+fn parse_go_test(output: &str) -> Vec<TestCase> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let (outcome, rest) = if let Some(rest) = line.strip_prefix("--- PASS: ") {
+                (TestOutcome::Passed, rest)
+            } else if let Some(rest) = line.strip_prefix("--- FAIL: ") {
+                (TestOutcome::Failed, rest)
+            } else if let Some(rest) = line.strip_prefix("--- SKIP: ") {
+                (TestOutcome::Skipped, rest)
+            } else {
+                return None;
+            };
+
+            let (name, elapsed) = match rest.split_once(" (") {
+                Some((name, elapsed)) => (name, elapsed.trim_end_matches([')', 's'])),
+                None => (rest.trim(), ""),
+            };
+            Some(TestCase {
+                duration: elapsed.parse().ok().map(Duration::from_secs_f64),
+                ..TestCase::new(name, outcome)
+            })
+        })
+        .collect()
+}
+
+/// parses the events printed by `go test -json`
+fn parse_go_json(output: &str) -> Vec<TestCase> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Event {
+        action: String,
+        test: Option<String>,
+        elapsed: Option<f64>,
+    }
+
+    output
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .filter_map(|line| serde_json::from_str::<Event>(line).ok())
+        .filter_map(|event| {
+            let outcome = match event.action.as_str() {
+                "pass" => TestOutcome::Passed,
+                "fail" => TestOutcome::Failed,
+                "skip" => TestOutcome::Skipped,
+                _ => return None,
+            };
+            Some(TestCase {
+                duration: event.elapsed.map(Duration::from_secs_f64),
+                ..TestCase::new(&event.test?, outcome)
+            })
+        })
+        .collect()
+}
+
+/// parses `test_name (module.Class.test_name) ... ok` lines printed by unittest in verbose mode
+fn parse_unittest(output: &str) -> Vec<TestCase> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (test, result) = line.rsplit_once(" ... ")?;
+            let (method, id) = test.split_once(" (")?;
+            let id = id.strip_suffix(')')?;
+            if method.contains(char::is_whitespace) {
+                return None;
+            }
+
+            let outcome = match result.trim() {
+                "ok" | "expected failure" => TestOutcome::Passed,
+                "FAIL" | "ERROR" | "unexpected success" => TestOutcome::Failed,
+                r if r.starts_with("skipped") => TestOutcome::Skipped,
+                _ => return None,
+            };
+
+            // python 3.11+ includes the method in the id, older versions only the class
+            let name = if id.ends_with(&format!(".{}", method)) {
+                id.to_string()
+            } else {
+                format!("{}.{}", id, method)
+            };
+            Some(TestCase::new(&name, outcome))
+        })
+        .collect()
+}
+
+/// parses `test_file.py::test_name PASSED [ 50%]` lines printed by pytest in verbose mode
+fn parse_pytest(output: &str) -> Vec<TestCase> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let name = words.next().filter(|w| w.contains("::"))?;
+            let outcome = match words.next()? {
+                "PASSED" | "XFAIL" => TestOutcome::Passed,
+                "FAILED" | "ERROR" | "XPASS" => TestOutcome::Failed,
+                "SKIPPED" => TestOutcome::Skipped,
+                _ => return None,
+            };
+            Some(TestCase::new(name, outcome))
+        })
+        .collect()
+}
+
+/// parses `ok 1 - description` and `not ok 2 - description` TAP lines, honoring SKIP and TODO
+fn parse_tap(output: &str) -> Vec<TestCase> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let (passed, rest) = if let Some(rest) = line.strip_prefix("not ok") {
+                (false, rest)
+            } else if let Some(rest) = line.strip_prefix("ok") {
+                (true, rest)
+            } else {
+                return None;
+            };
+            if !rest.is_empty() && !rest.starts_with(' ') {
+                return None;
+            }
+
+            let (description, directive) = match rest.split_once(" # ") {
+                Some((description, directive)) => (description, directive.to_uppercase()),
+                None => (rest, String::new()),
+            };
+            let name = description
+                .trim()
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .trim_start()
+                .trim_start_matches("- ")
+                .trim();
+
+            let outcome = if directive.starts_with("SKIP") || directive.starts_with("TODO") {
+                TestOutcome::Skipped
+            } else if passed {
+                TestOutcome::Passed
+            } else {
+                TestOutcome::Failed
+            };
+            Some(TestCase::new(name, outcome))
+        })
+        .enumerate()
+        .map(|(i, mut test)| {
+            // descriptions are optional in TAP, fall back to the test number
+            if test.name.is_empty() {
+                test.name = format!("test {}", i + 1);
+            }
+            test
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcomes(tests: &[TestCase]) -> Vec<(&str, TestOutcome)> {
+        tests.iter().map(|t| (t.name.as_str(), t.outcome)).collect()
+    }
+
+    #[test]
+    fn go_test_verbose() {
+        let output = "\
+=== RUN   TestLRU
+--- PASS: TestLRU (0.00s)
+=== RUN   TestEvict
+    LRU_test.go:20: expected 3, got 4
+--- FAIL: TestEvict (0.25s)
+=== RUN   TestTable
+=== RUN   TestTable/empty
+    --- SKIP: TestTable/empty (0.00s)
+--- PASS: TestTable (0.00s)
+FAIL
+exit status 1
+";
+        let tests = parse_go_test(output);
+        assert_eq!(
+            outcomes(&tests),
+            [
+                ("TestLRU", TestOutcome::Passed),
+                ("TestEvict", TestOutcome::Failed),
+                ("TestTable/empty", TestOutcome::Skipped),
+                ("TestTable", TestOutcome::Passed),
+            ]
+        );
+        assert_eq!(tests[1].duration, Some(Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn go_test_json() {
+        let output = r#"{"Action":"run","Test":"TestLRU"}
+{"Action":"output","Test":"TestLRU","Output":"--- PASS: TestLRU (0.00s)\n"}
+{"Action":"pass","Test":"TestLRU","Elapsed":0.5}
+{"Action":"fail","Test":"TestEvict","Elapsed":0}
+{"Action":"fail","Elapsed":0.6}
+"#;
+        let tests = parse_go_json(output);
+        assert_eq!(
+            outcomes(&tests),
+            [
+                ("TestLRU", TestOutcome::Passed),
+                ("TestEvict", TestOutcome::Failed)
+            ]
+        );
+        assert_eq!(tests[0].duration, Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn unittest_verbose() {
+        let output = "\
+test_get (test_lru.TestLRU.test_get) ... ok
+test_evict (test_lru.TestLRU) ... FAIL
+test_update (test_lru.TestLRU.test_update) ... ERROR
+test_resize (test_lru.TestLRU.test_resize) ... skipped 'not implemented'
+
+======================================================================
+FAIL: test_evict (test_lru.TestLRU)
+----------------------------------------------------------------------
+Ran 4 tests in 0.001s
+";
+        assert_eq!(
+            outcomes(&parse_unittest(output)),
+            [
+                ("test_lru.TestLRU.test_get", TestOutcome::Passed),
+                ("test_lru.TestLRU.test_evict", TestOutcome::Failed),
+                ("test_lru.TestLRU.test_update", TestOutcome::Failed),
+                ("test_lru.TestLRU.test_resize", TestOutcome::Skipped),
+            ]
+        );
+    }
+
+    #[test]
+    fn pytest_verbose() {
+        let output = "\
+============================= test session starts ==============================
+test_lru.py::test_get PASSED                                             [ 33%]
+test_lru.py::TestLRU::test_evict FAILED                                  [ 66%]
+test_lru.py::test_resize SKIPPED (not implemented)                       [100%]
+=========================== short test summary info ============================
+FAILED test_lru.py::TestLRU::test_evict - assert 4 == 3
+";
+        assert_eq!(
+            outcomes(&parse_pytest(output)),
+            [
+                ("test_lru.py::test_get", TestOutcome::Passed),
+                ("test_lru.py::TestLRU::test_evict", TestOutcome::Failed),
+                ("test_lru.py::test_resize", TestOutcome::Skipped),
+            ]
+        );
+    }
+
+    #[test]
+    fn tap() {
+        let output = "\
+TAP version 13
+1..5
+ok 1 - push adds to the tail
+not ok 2 - pop removes from the head
+ok 3 # SKIP not ready
+not ok 4 - peek # TODO
+ok
+okay this is not a test
+";
+        assert_eq!(
+            outcomes(&parse_tap(output)),
+            [
+                ("push adds to the tail", TestOutcome::Passed),
+                ("pop removes from the head", TestOutcome::Failed),
+                ("test 3", TestOutcome::Skipped),
+                ("peek", TestOutcome::Skipped),
+                ("test 5", TestOutcome::Passed),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_tests_found_is_none() {
+        let path = Path::new("does/not/exist");
+        assert_eq!(ResultParser::GoTest.parse("hello world", path), None);
+        assert_eq!(ResultParser::Auto.parse("hello world", path), None);
+        assert_eq!(
+            ResultParser::Off.parse("--- PASS: TestA (0.00s)", path),
+            None
+        );
+    }
+
+    #[test]
+    fn auto_detects_the_format() {
+        let path = Path::new("does/not/exist");
+        let tests = ResultParser::Auto
+            .parse("--- PASS: TestA (0.00s)\n", path)
+            .unwrap();
+        assert_eq!(outcomes(&tests), [("TestA", TestOutcome::Passed)]);
+
+        let tests = ResultParser::Auto.parse("ok 1 - works\n", path).unwrap();
+        assert_eq!(outcomes(&tests), [("works", TestOutcome::Passed)]);
+    }
+}
@@ -0,0 +1,364 @@
+//! Pools of katas for `katac random`: the katas it can pick from, how likely each one is, the
+//! tags the picks must include and how recently done katas are left out.
+
+use crate::HistoryRecord;
+use chrono::{Duration, NaiveDate};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+/// a kata of a pool, either just its name or its name and weight
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum PoolKata {
+    Name(String),
+    Weighted { name: String, weight: f64 },
+}
+
+impl PoolKata {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            PoolKata::Name(name) | PoolKata::Weighted { name, .. } => name,
+        }
+    }
+
+    fn weight(&self) -> Option<f64> {
+        match self {
+            PoolKata::Name(_) => None,
+            PoolKata::Weighted { weight, .. } => Some(*weight),
+        }
+    }
+}
+
+/// at least `at_least` of the picked katas must have the tag
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct TagRequirement {
+    pub tag: String,
+    #[serde(default = "one")]
+    pub at_least: usize,
+}
+
+fn one() -> usize {
+    1
+}
+
+/// a `[pools.<name>]` table of the config file
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct PoolConfig {
+    /// katas of the pool, every kata in the katas folder if empty
+    #[serde(default)]
+    pub katas: Vec<PoolKata>,
+    #[serde(default)]
+    pub require: Vec<TagRequirement>,
+    /// leave out the katas done in the last N days
+    pub exclude_days: Option<u32>,
+}
+
+/// a kata that can be picked
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Candidate {
+    pub name: String,
+    pub weight: f64,
+    pub tags: Vec<String>,
+}
+
+impl Candidate {
+    /// returns a candidate with the weight of the pool entry, or the default one
+    pub(crate) fn new(kata: &PoolKata, default_weight: Option<f64>, tags: Vec<String>) -> Self {
+        Candidate {
+            name: kata.name().to_string(),
+            weight: kata.weight().or(default_weight).unwrap_or(1.0),
+            tags,
+        }
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+/// the katas `katac random` picks from
+#[derive(Debug, Clone)]
+pub(crate) struct Pool {
+    /// how the pool is named in errors, e.g. `pool 'hard'` or `the katas folder`
+    pub description: String,
+    pub candidates: Vec<Candidate>,
+    pub require: Vec<TagRequirement>,
+    pub exclude_days: Option<u32>,
+}
+
+impl Pool {
+    /// removes the katas done today or in the `exclude_days` days before, returns their names
+    pub(crate) fn exclude_recent(
+        &mut self,
+        records: &[HistoryRecord],
+        today: NaiveDate,
+    ) -> Vec<String> {
+        let days = match self.exclude_days {
+            Some(days) => days,
+            None => return Vec::new(),
+        };
+        let since = today - Duration::days(days as i64);
+        let recent: HashSet<&str> = records
+            .iter()
+            .filter(|r| r.timestamp.date_naive() >= since)
+            .map(|r| r.kata.as_str())
+            .collect();
+
+        let (excluded, kept) = self
+            .candidates
+            .drain(..)
+            .partition(|c| recent.contains(c.name.as_str()));
+        self.candidates = kept;
+        excluded.into_iter().map(|c: Candidate| c.name).collect()
+    }
+
+    /// picks `number` katas that meet the tag requirements of the pool. `pick` chooses one of
+    /// the eligible candidates and returns its index
+    pub(crate) fn select(
+        &self,
+        number: usize,
+        mut pick: impl FnMut(&[&Candidate]) -> usize,
+    ) -> Result<Vec<String>, String> {
+        if number > self.candidates.len() {
+            return Err(format!(
+                "{} has only {} kata(s) to pick from, can't pick {}",
+                self.description,
+                self.candidates.len(),
+                number
+            ));
+        }
+        for requirement in &self.require {
+            let tag = requirement.tag.as_str();
+            let available = self.candidates.iter().filter(|c| c.has_tag(tag)).count();
+            if available < requirement.at_least {
+                return Err(format!(
+                    "{} needs at least {} kata(s) tagged '{}' but only {} can be picked",
+                    self.description, requirement.at_least, tag, available
+                ));
+            }
+        }
+
+        let candidates: Vec<&Candidate> = self.candidates.iter().collect();
+        if let Some(picked) = self.complete(number, candidates.clone(), Vec::new(), &mut pick) {
+            return Ok(picked.into_iter().map(|c| c.name.clone()).collect());
+        }
+        // every requirement can be met on its own, so all of them need more katas
+        let needed = (number + 1..=candidates.len())
+            .find(|&n| {
+                self.complete(n, candidates.clone(), Vec::new(), &mut |_| 0)
+                    .is_some()
+            })
+            .unwrap_or(candidates.len());
+        Err(format!(
+            "{} needs at least {} katas to meet its tag requirements, can't pick only {}",
+            self.description, needed, number
+        ))
+    }
+
+    /// adds katas of `remaining` to `picked` until it has `number` katas that meet the tag
+    /// requirements, None if it can't. The katas of the first requirement that isn't met are
+    /// tried in the order `pick` chooses them, until one leads to a selection that meets the
+    /// others too
+    fn complete<'a>(
+        &self,
+        number: usize,
+        mut remaining: Vec<&'a Candidate>,
+        mut picked: Vec<&'a Candidate>,
+        pick: &mut impl FnMut(&[&Candidate]) -> usize,
+    ) -> Option<Vec<&'a Candidate>> {
+        let missing = |r: &TagRequirement| r.at_least.saturating_sub(tagged(&picked, &r.tag));
+        if self
+            .require
+            .iter()
+            .any(|r| missing(r) > tagged(&remaining, &r.tag) || picked.len() + missing(r) > number)
+        {
+            return None;
+        }
+
+        let Some(requirement) = self.require.iter().find(|r| missing(r) > 0) else {
+            if picked.len() + remaining.len() < number {
+                return None;
+            }
+            while picked.len() < number {
+                let chosen = pick(&remaining).min(remaining.len() - 1);
+                picked.push(remaining.remove(chosen));
+            }
+            return Some(picked);
+        };
+        loop {
+            let indexes: Vec<usize> = (0..remaining.len())
+                .filter(|&i| remaining[i].has_tag(&requirement.tag))
+                .collect();
+            if indexes.is_empty() {
+                return None;
+            }
+            let choices: Vec<&Candidate> = indexes.iter().map(|&i| remaining[i]).collect();
+            // a kata that doesn't lead to a selection isn't in any, so it isn't tried again
+            let chosen = remaining.remove(indexes[pick(&choices).min(indexes.len() - 1)]);
+            let mut with_chosen = picked.clone();
+            with_chosen.push(chosen);
+            if let Some(picked) = self.complete(number, remaining.clone(), with_chosen, pick) {
+                return Some(picked);
+            }
+        }
+    }
+}
+
+/// returns how many of the candidates have the tag
+fn tagged(candidates: &[&Candidate], tag: &str) -> usize {
+    candidates.iter().filter(|c| c.has_tag(tag)).count()
+}
+
+/// returns a pick function that chooses a candidate at random, proportionally to its weight
+pub(crate) fn weighted_pick(rng: &mut impl Rng) -> impl FnMut(&[&Candidate]) -> usize + '_ {
+    move |choices| match WeightedIndex::new(choices.iter().map(|c| c.weight.max(0.0))) {
+        Ok(index) => index.sample(rng),
+        // every weight is zero
+        Err(_) => rng.gen_range(0..choices.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HistoryEvent;
+    use chrono::DateTime;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn candidate(name: &str, weight: f64, tags: &[&str]) -> Candidate {
+        Candidate {
+            name: name.to_string(),
+            weight,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    fn pool(require: &[(&str, usize)]) -> Pool {
+        Pool {
+            description: "pool 'hard'".to_string(),
+            candidates: vec![
+                candidate("LRU", 1.0, &["cache"]),
+                candidate("Trie", 1.0, &["tree"]),
+                candidate("UnionFind", 1.0, &["graph"]),
+                candidate("Map", 1.0, &[]),
+            ],
+            require: require
+                .iter()
+                .map(|(tag, at_least)| TagRequirement {
+                    tag: tag.to_string(),
+                    at_least: *at_least,
+                })
+                .collect(),
+            exclude_days: None,
+        }
+    }
+
+    #[test]
+    fn pool_config() {
+        let config: PoolConfig = toml::from_str(
+            r#"
+katas = ["Trie", { name = "LRU", weight = 3 }]
+require = [{ tag = "tree" }, { tag = "graph", at_least = 2 }]
+exclude_days = 3
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.katas[0], PoolKata::Name("Trie".to_string()));
+        assert_eq!(
+            Candidate::new(&config.katas[0], Some(2.0), vec![]).weight,
+            2.0
+        );
+        assert_eq!(
+            Candidate::new(&config.katas[1], Some(2.0), vec![]).weight,
+            3.0
+        );
+        assert_eq!(config.require[0].at_least, 1);
+        assert_eq!(config.require[1].at_least, 2);
+        assert_eq!(config.exclude_days, Some(3));
+    }
+
+    #[test]
+    fn requirements_are_picked_first() {
+        let pool = pool(&[("graph", 1), ("tree", 1)]);
+        // always the first eligible candidate
+        let picked = pool.select(3, |_| 0).unwrap();
+        assert_eq!(picked, ["UnionFind", "Trie", "LRU"]);
+    }
+
+    #[test]
+    fn requirements_are_met_by_katas_with_several_tags() {
+        let mut pool = pool(&[("tree", 1), ("graph", 1)]);
+        pool.candidates = vec![
+            candidate("Trie", 1.0, &["tree"]),
+            candidate("Dijkstra", 1.0, &["graph"]),
+            candidate("TreeGraph", 1.0, &["tree", "graph"]),
+        ];
+        // Trie is chosen first for tree, but then graph can't be met with a single kata
+        assert_eq!(pool.select(1, |_| 0).unwrap(), ["TreeGraph"]);
+
+        pool.require[0].at_least = 2;
+        pool.candidates.push(candidate("Heap", 1.0, &["tree"]));
+        let picked = pool.select(2, |_| 0).unwrap();
+        assert_eq!(picked, ["Trie", "TreeGraph"]);
+        // whichever kata is chosen first
+        let picked = pool.select(2, |choices| choices.len() - 1).unwrap();
+        assert_eq!(picked, ["Heap", "TreeGraph"]);
+    }
+
+    #[test]
+    fn unmet_requirements_are_errors() {
+        let err = pool(&[("tree", 2)]).select(3, |_| 0).unwrap_err();
+        assert_eq!(
+            err,
+            "pool 'hard' needs at least 2 kata(s) tagged 'tree' but only 1 can be picked"
+        );
+
+        let err = pool(&[("tree", 1), ("graph", 1)])
+            .select(1, |_| 0)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "pool 'hard' needs at least 2 katas to meet its tag requirements, can't pick only 1"
+        );
+
+        let err = pool(&[]).select(5, |_| 0).unwrap_err();
+        assert_eq!(
+            err,
+            "pool 'hard' has only 4 kata(s) to pick from, can't pick 5"
+        );
+    }
+
+    #[test]
+    fn weights() {
+        let mut pool = pool(&[]);
+        pool.candidates[0].weight = 0.0;
+        pool.candidates[1].weight = 0.0;
+        pool.candidates[2].weight = 0.0;
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..10 {
+            assert_eq!(pool.select(1, weighted_pick(&mut rng)).unwrap(), ["Map"]);
+        }
+    }
+
+    #[test]
+    fn excludes_recent_katas() {
+        let record = |date: &str, kata: &str| HistoryRecord {
+            timestamp: DateTime::parse_from_rfc3339(&format!("{}T09:00:00Z", date)).unwrap(),
+            event: HistoryEvent::Start,
+            day: 1,
+            kata: kata.to_string(),
+            language: None,
+            passed: None,
+            duration_ms: None,
+        };
+        let records = [record("2024-03-01", "LRU"), record("2024-03-04", "Trie")];
+        let today = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+
+        let mut pool = pool(&[]);
+        pool.exclude_days = Some(2);
+        assert_eq!(pool.exclude_recent(&records, today), ["Trie"]);
+        assert_eq!(pool.candidates.len(), 3);
+    }
+}
@@ -0,0 +1,134 @@
+//! The process groups of the katas that run with a timeout. They run in their own process
+//! group, so everything they start is killed along with them. A kata that runs on its own gets
+//! the terminal, so it reads stdin and Ctrl-C reaches it, and katac takes the terminal back
+//! once it exits. The katas that run in parallel stay in the background: Ctrl-C only reaches
+//! katac, whose signal handlers can kill their groups with `kill_running_katas`.
+
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// how many katas can run in their own process group at the same time, the others share the
+/// group of katac
+const MAX_GROUPS: usize = 64;
+
+/// the process groups of the running katas, 0 for the free slots. Read by the signal handler,
+/// so it can't be behind a lock
+static GROUPS: [AtomicI32; MAX_GROUPS] = [const { AtomicI32::new(0) }; MAX_GROUPS];
+
+/// a slot of the process group of a kata, freed when dropped
+pub(crate) struct GroupSlot(usize);
+
+impl GroupSlot {
+    /// reserves a slot for a new process group, None if every slot is taken
+    pub(crate) fn reserve() -> Option<GroupSlot> {
+        // -1 marks the slot as reserved until the kata is spawned
+        GROUPS
+            .iter()
+            .position(|g| {
+                g.compare_exchange(0, -1, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+            })
+            .map(GroupSlot)
+    }
+
+    /// records the process group of the spawned kata, led by its pid
+    pub(crate) fn set(&self, pid: u32) {
+        GROUPS[self.0].store(pid as i32, Ordering::SeqCst);
+    }
+}
+
+impl Drop for GroupSlot {
+    fn drop(&mut self) {
+        GROUPS[self.0].store(0, Ordering::SeqCst);
+    }
+}
+
+/// returns whether katac is in the foreground of the terminal of its stdin
+pub(crate) fn owns_terminal() -> bool {
+    // SAFETY: isatty, tcgetpgrp and getpgrp have no memory safety requirements
+    unsafe {
+        libc::isatty(libc::STDIN_FILENO) == 1
+            && libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp()
+    }
+}
+
+/// makes the command put its process group in the foreground of the terminal before it starts,
+/// the command must lead its own process group
+pub(crate) fn give_terminal(cmd: &mut Command) {
+    let give = || {
+        // the group of the command is still in the background, a blocked SIGTTOU lets it take
+        // the terminal instead of stopping it
+        with_sigttou_blocked(|| {
+            // SAFETY: tcsetpgrp and getpid are async-signal-safe
+            unsafe { libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpid()) }
+        })
+    };
+    // SAFETY: the closure only calls async-signal-safe functions
+    unsafe {
+        cmd.pre_exec(move || match give() {
+            -1 => Err(std::io::Error::last_os_error()),
+            _ => Ok(()),
+        });
+    }
+}
+
+/// puts the process group of katac back in the foreground of the terminal
+pub(crate) fn take_terminal() {
+    with_sigttou_blocked(|| {
+        // SAFETY: tcsetpgrp and getpgrp have no memory safety requirements
+        unsafe { libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp()) }
+    });
+}
+
+/// sends SIGINT to katac, for a Ctrl-C that only reached the kata in the foreground
+pub(crate) fn interrupt() {
+    // SAFETY: kill and getpid have no memory safety requirements
+    unsafe {
+        libc::kill(libc::getpid(), libc::SIGINT);
+    }
+}
+
+/// runs `f` with SIGTTOU blocked in the calling thread
+fn with_sigttou_blocked<T>(f: impl FnOnce() -> T) -> T {
+    // SAFETY: the signal sets are initialized by sigemptyset before use
+    unsafe {
+        let mut block: libc::sigset_t = std::mem::zeroed();
+        let mut previous: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut block);
+        libc::sigaddset(&mut block, libc::SIGTTOU);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &block, &mut previous);
+        let result = f();
+        libc::pthread_sigmask(libc::SIG_SETMASK, &previous, std::ptr::null_mut());
+        result
+    }
+}
+
+/// kills the process groups of the running katas that have their own, everything they started
+/// included. Only async-signal-safe functions are called, so it can be called from a signal
+/// handler, e.g. before katac exits on Ctrl-C
+pub fn kill_running_katas() {
+    for group in &GROUPS {
+        let pid = group.load(Ordering::SeqCst);
+        if pid > 0 {
+            // SAFETY: kill has no memory safety requirements
+            unsafe {
+                libc::kill(-pid, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_are_freed_when_dropped() {
+        let slot = GroupSlot::reserve().unwrap();
+        slot.set(4242);
+        assert!(GROUPS.iter().any(|g| g.load(Ordering::SeqCst) == 4242));
+        drop(slot);
+        assert!(!GROUPS.iter().any(|g| g.load(Ordering::SeqCst) == 4242));
+    }
+}
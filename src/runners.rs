@@ -0,0 +1,335 @@
+//! How `katac run` runs a kata: the runners, tried in order of priority until one finds
+//! something to run in the kata folder, e.g. its Makefile or its run.sh.
+
+use crate::command_line::recipe_command;
+use crate::{get_embedded_run_command, is_embedded_kata, kata_manifest, MANIFEST_FILE};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// builds the command that runs a kata
+pub trait Runner: Send + Sync {
+    /// name of the runner, used to pick it with `runner` under [kata.<name>] in the config file
+    fn name(&self) -> &str;
+
+    /// returns the command that runs the kata in the given folder, None if the runner can't run
+    /// it. The command runs inside the kata folder. Notes about how the kata is run are written
+    /// to `out`
+    fn command(&self, kata_path: &Path, out: &mut dyn Write) -> Option<Command>;
+}
+
+/// the run command of the kata.toml
+pub struct ExplicitRunner;
+
+impl Runner for ExplicitRunner {
+    fn name(&self) -> &str {
+        "command"
+    }
+
+    fn command(&self, kata_path: &Path, out: &mut dyn Write) -> Option<Command> {
+        let run = kata_manifest(kata_path).run?;
+        let command = recipe_command(std::slice::from_ref(&run))?;
+        let _ = writeln!(out, "Running {} command: {}", MANIFEST_FILE, run);
+        Some(command)
+    }
+}
+
+/// `make run` with the Makefile of the kata, when make is installed
+#[derive(Default)]
+pub struct MakeRunner {
+    installed: OnceLock<bool>,
+}
+
+impl Runner for MakeRunner {
+    fn name(&self) -> &str {
+        "make"
+    }
+
+    fn command(&self, kata_path: &Path, _out: &mut dyn Write) -> Option<Command> {
+        if !kata_path.join("Makefile").exists() || !is_installed("make", &self.installed) {
+            return None;
+        }
+        let mut command = Command::new("make");
+        command.arg("run").arg("-s");
+        Some(command)
+    }
+}
+
+/// the run recipe of the Makefile of an embedded kata, for embedded katas without a Makefile
+pub struct EmbeddedRunner;
+
+impl Runner for EmbeddedRunner {
+    fn name(&self) -> &str {
+        "embedded"
+    }
+
+    fn command(&self, kata_path: &Path, out: &mut dyn Write) -> Option<Command> {
+        let kata_name = kata_path.file_name()?.to_str()?;
+        let (language, _) = is_embedded_kata(kata_name)?;
+        let recipe = get_embedded_run_command(&language, kata_name)?;
+        let command = recipe_command(&recipe)?;
+        let _ = writeln!(
+            out,
+            "Running embedded kata command: {}",
+            recipe.join(" && ")
+        );
+        Some(command)
+    }
+}
+
+/// `just run` with the justfile of the kata, when just is installed
+#[derive(Default)]
+pub struct JustRunner {
+    installed: OnceLock<bool>,
+}
+
+impl Runner for JustRunner {
+    fn name(&self) -> &str {
+        "just"
+    }
+
+    fn command(&self, kata_path: &Path, _out: &mut dyn Write) -> Option<Command> {
+        let has_justfile = ["justfile", "Justfile", ".justfile"]
+            .iter()
+            .any(|file| kata_path.join(file).exists());
+        if !has_justfile || !is_installed("just", &self.installed) {
+            return None;
+        }
+        let mut command = Command::new("just");
+        command.arg("run");
+        Some(command)
+    }
+}
+
+/// `cargo test` for katas with a Cargo.toml
+pub struct CargoRunner;
+
+impl Runner for CargoRunner {
+    fn name(&self) -> &str {
+        "cargo"
+    }
+
+    fn command(&self, kata_path: &Path, _out: &mut dyn Write) -> Option<Command> {
+        if !kata_path.join("Cargo.toml").exists() {
+            return None;
+        }
+        let mut command = Command::new("cargo");
+        command.arg("test");
+        Some(command)
+    }
+}
+
+/// `npm test` for katas with a test script in their package.json
+pub struct NpmRunner;
+
+impl Runner for NpmRunner {
+    fn name(&self) -> &str {
+        "npm"
+    }
+
+    fn command(&self, kata_path: &Path, _out: &mut dyn Write) -> Option<Command> {
+        let package = std::fs::read_to_string(kata_path.join("package.json")).ok()?;
+        let package: serde_json::Value = serde_json::from_str(&package).ok()?;
+        package.get("scripts")?.get("test")?;
+
+        let mut command = Command::new(if cfg!(target_os = "windows") {
+            "npm.cmd"
+        } else {
+            "npm"
+        });
+        command.arg("test");
+        Some(command)
+    }
+}
+
+/// the run.sh of the kata, except on Windows
+pub struct ShellRunner;
+
+impl Runner for ShellRunner {
+    fn name(&self) -> &str {
+        "shell"
+    }
+
+    fn command(&self, kata_path: &Path, _out: &mut dyn Write) -> Option<Command> {
+        if cfg!(target_os = "windows") || !kata_path.join("run.sh").exists() {
+            return None;
+        }
+        let mut command = Command::new("sh");
+        command.arg("./run.sh");
+        Some(command)
+    }
+}
+
+/// the run.bat of the kata, on Windows
+pub struct BatchRunner;
+
+impl Runner for BatchRunner {
+    fn name(&self) -> &str {
+        "batch"
+    }
+
+    fn command(&self, kata_path: &Path, _out: &mut dyn Write) -> Option<Command> {
+        if !cfg!(target_os = "windows") || !kata_path.join("run.bat").exists() {
+            return None;
+        }
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg("run.bat");
+        Some(command)
+    }
+}
+
+/// returns whether the program can be run, checking it only once
+fn is_installed(program: &str, installed: &OnceLock<bool>) -> bool {
+    *installed.get_or_init(|| {
+        Command::new(program)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok()
+    })
+}
+
+/// the runners of a workspace, by priority
+pub struct Runners {
+    runners: Vec<Box<dyn Runner>>,
+}
+
+impl Default for Runners {
+    /// the built-in runners: the kata.toml run command, make, the embedded kata recipe, just,
+    /// cargo, npm, run.sh and run.bat
+    fn default() -> Self {
+        Runners {
+            runners: vec![
+                Box::new(ExplicitRunner),
+                Box::new(MakeRunner::default()),
+                Box::new(EmbeddedRunner),
+                Box::new(JustRunner::default()),
+                Box::new(CargoRunner),
+                Box::new(NpmRunner),
+                Box::new(ShellRunner),
+                Box::new(BatchRunner),
+            ],
+        }
+    }
+}
+
+impl std::fmt::Debug for Runners {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl Runners {
+    /// adds a runner before the others, replacing the one with the same name
+    pub fn register(&mut self, runner: Box<dyn Runner>) {
+        self.runners.retain(|r| r.name() != runner.name());
+        self.runners.insert(0, runner);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Runner> {
+        self.runners
+            .iter()
+            .find(|r| r.name() == name)
+            .map(|r| r.as_ref())
+    }
+
+    /// names of the runners, by priority
+    pub fn names(&self) -> Vec<&str> {
+        self.runners.iter().map(|r| r.name()).collect()
+    }
+
+    /// returns the command of the first runner that can run the kata in the given folder
+    pub fn detect(&self, kata_path: &Path, out: &mut dyn Write) -> Option<Command> {
+        self.runners
+            .iter()
+            .find_map(|runner| runner.command(kata_path, out))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    fn kata_dir(name: &str, files: &[(&str, &str)]) -> TempDir {
+        TempDir::with_files(&format!("runners_{}", name), files)
+    }
+
+    fn program(cmd: Option<Command>) -> Option<String> {
+        cmd.map(|cmd| cmd.get_program().to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn detects_by_priority() {
+        let runners = Runners::default();
+        let dir = kata_dir(
+            "priority",
+            &[("kata.toml", "run = \"go test ./...\""), ("Cargo.toml", "")],
+        );
+        let mut out = Vec::new();
+        assert_eq!(
+            program(runners.detect(&dir, &mut out)).as_deref(),
+            Some("go")
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Running kata.toml command: go test ./...\n"
+        );
+
+        fs::remove_file(dir.join("kata.toml")).unwrap();
+        assert_eq!(
+            program(runners.detect(&dir, &mut Vec::new())).as_deref(),
+            Some("cargo")
+        );
+    }
+
+    #[test]
+    fn npm_needs_a_test_script() {
+        let dir = kata_dir(
+            "npm",
+            &[("package.json", r#"{"scripts": {"start": "node ."}}"#)],
+        );
+        assert!(NpmRunner.command(&dir, &mut Vec::new()).is_none());
+        fs::write(
+            dir.join("package.json"),
+            r#"{"scripts": {"test": "node --test"}}"#,
+        )
+        .unwrap();
+        assert!(NpmRunner.command(&dir, &mut Vec::new()).is_some());
+    }
+
+    #[test]
+    fn nothing_to_run() {
+        let dir = kata_dir("nothing", &[("main.go", "package main")]);
+        assert!(Runners::default().detect(&dir, &mut Vec::new()).is_none());
+    }
+
+    struct Echo;
+
+    impl Runner for Echo {
+        fn name(&self) -> &str {
+            "cargo"
+        }
+
+        fn command(&self, _kata_path: &Path, _out: &mut dyn Write) -> Option<Command> {
+            Some(Command::new("echo"))
+        }
+    }
+
+    #[test]
+    fn registered_runners_come_first() {
+        let mut runners = Runners::default();
+        runners.register(Box::new(Echo));
+        assert_eq!(runners.names()[..2], ["cargo", "command"]);
+        assert_eq!(runners.names().iter().filter(|n| **n == "cargo").count(), 1);
+
+        let dir = kata_dir("registered", &[("kata.toml", "run = \"go test\"")]);
+        assert_eq!(
+            program(runners.detect(&dir, &mut Vec::new())).as_deref(),
+            Some("echo")
+        );
+    }
+}
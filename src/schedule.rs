@@ -0,0 +1,246 @@
+//! Spaced repetition for `katac random`: a Leitner style schedule where every kata that passes
+//! moves up a box and is due again after a longer interval, and every kata that fails goes back
+//! to the first box, due the next day.
+
+use crate::{HistoryEvent, HistoryRecord};
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// days until a kata is due again, by box
+const INTERVALS: [i64; 6] = [1, 2, 4, 8, 16, 32];
+
+/// how `katac random` picks katas
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RandomStrategy {
+    /// every kata has the same chance
+    #[default]
+    Uniform,
+    /// favor katas that are overdue or failed recently
+    Spaced,
+}
+
+/// how a day of practice of a kata ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Passed,
+    Failed,
+    /// it was never run with `katac run`
+    Done,
+}
+
+/// where a kata is in the schedule
+#[derive(Debug, Clone, PartialEq)]
+struct KataSchedule {
+    /// index in INTERVALS
+    level: usize,
+    last_date: NaiveDate,
+    last_outcome: Outcome,
+}
+
+impl KataSchedule {
+    fn interval(&self) -> i64 {
+        INTERVALS[self.level]
+    }
+
+    fn due(&self) -> NaiveDate {
+        self.last_date + chrono::Duration::days(self.interval())
+    }
+}
+
+/// a kata picked by the spaced strategy and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pick {
+    pub kata: String,
+    pub reason: String,
+}
+
+/// replays the history of every kata to find where it is in the schedule
+fn schedules(records: &[HistoryRecord]) -> BTreeMap<&str, KataSchedule> {
+    // outcome of each practice, a kata in a day
+    let mut practices: BTreeMap<(&str, u32), (NaiveDate, Outcome)> = BTreeMap::new();
+    let mut records: Vec<&HistoryRecord> = records.iter().collect();
+    records.sort_by_key(|r| r.timestamp);
+    for record in records {
+        let date = record.timestamp.date_naive();
+        let practice = practices
+            .entry((&record.kata, record.day))
+            .or_insert((date, Outcome::Done));
+        practice.0 = practice.0.max(date);
+        if record.event == HistoryEvent::Run {
+            practice.1 = match (practice.1, record.passed) {
+                (Outcome::Passed, _) | (_, Some(true)) => Outcome::Passed,
+                _ => Outcome::Failed,
+            };
+        }
+    }
+
+    let mut by_date: Vec<(&str, NaiveDate, Outcome)> = practices
+        .into_iter()
+        .map(|((kata, _), (date, outcome))| (kata, date, outcome))
+        .collect();
+    by_date.sort_by_key(|(_, date, _)| *date);
+
+    let mut schedules: BTreeMap<&str, KataSchedule> = BTreeMap::new();
+    for (kata, date, outcome) in by_date {
+        let level = match (schedules.get(kata), outcome) {
+            (_, Outcome::Failed) => 0,
+            (None, _) => 1,
+            (Some(s), _) => (s.level + 1).min(INTERVALS.len() - 1),
+        };
+        schedules.insert(
+            kata,
+            KataSchedule {
+                level,
+                last_date: date,
+                last_outcome: outcome,
+            },
+        );
+    }
+    schedules
+}
+
+/// returns how urgent a kata is, a kata is due at 1.0
+fn priority(schedule: Option<&KataSchedule>, today: NaiveDate) -> f64 {
+    match schedule {
+        None => 1.0,
+        Some(s) => {
+            let elapsed = (today - s.last_date).num_days() as f64;
+            let failed_boost = if s.last_outcome == Outcome::Failed {
+                1.0
+            } else {
+                0.0
+            };
+            elapsed / s.interval() as f64 + failed_boost
+        }
+    }
+}
+
+fn reason(schedule: Option<&KataSchedule>, today: NaiveDate) -> String {
+    let s = match schedule {
+        None => return "new, never practiced".to_string(),
+        Some(s) => s,
+    };
+    let last = match s.last_outcome {
+        Outcome::Passed => format!("passed on {}", s.last_date),
+        Outcome::Failed => format!("failed on {}", s.last_date),
+        Outcome::Done => format!("done on {}", s.last_date),
+    };
+    let overdue = (today - s.due()).num_days();
+    let due = match overdue {
+        0 => "due today".to_string(),
+        d if d > 0 => format!("overdue by {} day(s)", d),
+        _ => format!("not due until {}, picked to fill the set", s.due()),
+    };
+    format!("{}, {} (review every {} day(s))", due, last, s.interval())
+}
+
+/// picks the `number` most urgent katas of the candidates. Katas equally urgent keep the
+/// order of the candidates, so shuffle them first to break ties randomly
+pub fn pick_spaced(
+    candidates: &[String],
+    records: &[HistoryRecord],
+    today: NaiveDate,
+    number: usize,
+) -> Vec<Pick> {
+    let schedules = schedules(records);
+    let mut ranked: Vec<(&String, f64)> = candidates
+        .iter()
+        .map(|kata| (kata, priority(schedules.get(kata.as_str()), today)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranked
+        .into_iter()
+        .take(number)
+        .map(|(kata, _)| Pick {
+            kata: kata.clone(),
+            reason: reason(schedules.get(kata.as_str()), today),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn record(date: &str, day: u32, kata: &str, passed: Option<bool>) -> HistoryRecord {
+        HistoryRecord {
+            timestamp: DateTime::parse_from_rfc3339(&format!("{}T09:00:00Z", date)).unwrap(),
+            event: match passed {
+                Some(_) => HistoryEvent::Run,
+                None => HistoryEvent::Start,
+            },
+            day,
+            kata: kata.to_string(),
+            language: None,
+            passed,
+            duration_ms: None,
+        }
+    }
+
+    fn history() -> Vec<HistoryRecord> {
+        vec![
+            // passed twice, box 2: due 4 days after 2024-03-05
+            record("2024-03-01", 1, "Trie", Some(true)),
+            record("2024-03-05", 2, "Trie", Some(false)),
+            record("2024-03-05", 2, "Trie", Some(true)),
+            // failed, box 0: due the next day
+            record("2024-03-06", 3, "LRU", Some(false)),
+            // done by hand, box 1: due 2 days later
+            record("2024-03-02", 1, "Map", None),
+        ]
+    }
+
+    #[test]
+    fn levels() {
+        let records = history();
+        let schedules = schedules(&records);
+        assert_eq!(schedules["Trie"].level, 2);
+        assert_eq!(schedules["Trie"].due(), date("2024-03-09"));
+        assert_eq!(schedules["LRU"].level, 0);
+        assert_eq!(schedules["LRU"].last_outcome, Outcome::Failed);
+        assert_eq!(schedules["Map"].level, 1);
+        assert_eq!(schedules["Map"].last_outcome, Outcome::Done);
+    }
+
+    #[test]
+    fn favors_failed_and_overdue_katas() {
+        let candidates: Vec<String> = ["Trie", "UnionFind", "Map", "LRU"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let picks = pick_spaced(&candidates, &history(), date("2024-03-07"), 4);
+        let katas: Vec<&str> = picks.iter().map(|p| p.kata.as_str()).collect();
+        assert_eq!(katas, ["Map", "LRU", "UnionFind", "Trie"]);
+
+        assert_eq!(
+            picks[0].reason,
+            "overdue by 3 day(s), done on 2024-03-02 (review every 2 day(s))"
+        );
+        assert_eq!(
+            picks[1].reason,
+            "due today, failed on 2024-03-06 (review every 1 day(s))"
+        );
+        assert_eq!(picks[2].reason, "new, never practiced");
+        assert_eq!(
+            picks[3].reason,
+            "not due until 2024-03-09, picked to fill the set, passed on 2024-03-05 (review every 4 day(s))"
+        );
+    }
+
+    #[test]
+    fn picks_only_the_asked_number() {
+        let candidates = vec!["Trie".to_string(), "LRU".to_string()];
+        let picks = pick_spaced(&candidates, &history(), date("2024-03-07"), 1);
+        assert_eq!(picks.len(), 1);
+        assert_eq!(picks[0].kata, "LRU");
+    }
+}
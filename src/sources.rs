@@ -0,0 +1,547 @@
+//! Where kata templates come from: the examples embedded in katac, a folder, a tar or zip
+//! archive, or a local git repository. Every source has the same layout, one folder per
+//! language with one folder per kata inside.
+
+use crate::{
+    embedded_manifest, extract_archive, kata_manifest, Error, KataManifest, EXAMPLE_KATAS,
+};
+use fs_extra::dir::CopyOptions;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// name of the examples embedded in katac, as a source
+pub const EMBEDDED_SOURCE: &str = "embedded";
+
+/// a kata template of a source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pub language: String,
+    pub kata: String,
+}
+
+/// somewhere kata templates can be listed and copied from
+pub trait KataSource: Send + Sync {
+    /// name of the source, e.g. `embedded` or the path of its folder
+    fn name(&self) -> String;
+
+    /// returns the templates of the source, sorted by kata and then by language
+    fn templates(&self) -> Result<Vec<Template>, Error>;
+
+    /// returns the kata.toml of a template, an empty one if it has none
+    fn manifest(&self, template: &Template) -> KataManifest;
+
+    /// copies a template to dest, which must not exist yet
+    fn fetch(&self, template: &Template, dest: &Path) -> Result<(), Error>;
+
+    /// returns the template of a kata, the first language that has it
+    fn find(&self, kata: &str) -> Result<Option<Template>, Error> {
+        Ok(self.templates()?.into_iter().find(|t| t.kata == kata))
+    }
+}
+
+impl std::fmt::Debug for dyn KataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "KataSource({})", self.name())
+    }
+}
+
+/// opens the source of the given spec: `embedded`, a path ending in `.git` for a git
+/// repository, a `.zip`, `.tar`, `.tar.gz` or `.tgz` archive, or else a folder
+pub fn open_source(spec: &str) -> Box<dyn KataSource> {
+    if spec == EMBEDDED_SOURCE {
+        return Box::new(EmbeddedSource);
+    }
+    if spec.trim_end_matches('/').ends_with(".git") {
+        return Box::new(GitSource::new(spec));
+    }
+    if archive_stem(Path::new(spec)).is_some() {
+        return Box::new(ArchiveSource::new(spec));
+    }
+    Box::new(DirSource::new(spec))
+}
+
+/// sorts templates by kata name first, then by language
+fn sort_templates(templates: &mut [Template]) {
+    templates.sort_by(|a, b| (&a.kata, &a.language).cmp(&(&b.kata, &b.language)));
+}
+
+/// the example katas embedded in katac
+pub struct EmbeddedSource;
+
+impl KataSource for EmbeddedSource {
+    fn name(&self) -> String {
+        EMBEDDED_SOURCE.to_string()
+    }
+
+    fn templates(&self) -> Result<Vec<Template>, Error> {
+        let mut templates = Vec::new();
+        for language_dir in EXAMPLE_KATAS.dirs() {
+            let Some(language) = language_dir.path().file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            for kata_dir in language_dir.dirs() {
+                if let Some(kata) = kata_dir.path().file_name().and_then(|n| n.to_str()) {
+                    templates.push(Template {
+                        language: language.to_string(),
+                        kata: kata.to_string(),
+                    });
+                }
+            }
+        }
+        sort_templates(&mut templates);
+        Ok(templates)
+    }
+
+    fn manifest(&self, template: &Template) -> KataManifest {
+        embedded_manifest(&template.language, &template.kata)
+    }
+
+    fn fetch(&self, template: &Template, dest: &Path) -> Result<(), Error> {
+        let path = format!("{}/{}", template.language, template.kata);
+        let dir = EXAMPLE_KATAS
+            .get_dir(&path)
+            .ok_or_else(|| Error::KataNotFound {
+                kata: template.kata.clone(),
+                location: format!("the embedded {} katas", template.language),
+            })?;
+        copy_embedded_dir(dir, dest).map_err(Error::io(format!(
+            "failed to copy the embedded kata [{}] {} to {}",
+            template.language,
+            template.kata,
+            dest.display()
+        )))
+    }
+}
+
+/// copies an embedded folder to dest, run.sh files are made executable
+fn copy_embedded_dir(dir: &include_dir::Dir, dest: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for file in dir.files() {
+        let Some(name) = file.path().file_name() else {
+            continue;
+        };
+        let file_path = dest.join(name);
+        fs::write(&file_path, file.contents())?;
+
+        #[cfg(unix)]
+        if name == "run.sh" {
+            use std::os::unix::prelude::PermissionsExt;
+            fs::set_permissions(&file_path, fs::Permissions::from_mode(0o755))?;
+        }
+    }
+    for subdir in dir.dirs() {
+        if let Some(name) = subdir.path().file_name() {
+            copy_embedded_dir(subdir, &dest.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+/// a folder of example katas
+pub struct DirSource {
+    dir: PathBuf,
+}
+
+impl DirSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        DirSource { dir: dir.into() }
+    }
+
+    fn template_path(&self, template: &Template) -> PathBuf {
+        self.dir.join(&template.language).join(&template.kata)
+    }
+}
+
+impl KataSource for DirSource {
+    fn name(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn templates(&self) -> Result<Vec<Template>, Error> {
+        let entries = fs::read_dir(&self.dir).map_err(Error::io(format!(
+            "failed to read the examples directory {}",
+            self.dir.display()
+        )))?;
+
+        let sub_dirs = |path: &Path| -> Vec<String> {
+            let Ok(entries) = fs::read_dir(path) else {
+                return Vec::new();
+            };
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .collect()
+        };
+        let mut templates = Vec::new();
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Ok(language) = entry.file_name().into_string() else {
+                continue;
+            };
+            // hidden folders, e.g. .git, aren't languages
+            if language.starts_with('.') || !entry.path().is_dir() {
+                continue;
+            }
+            for kata in sub_dirs(&entry.path()) {
+                templates.push(Template {
+                    language: language.clone(),
+                    kata,
+                });
+            }
+        }
+        sort_templates(&mut templates);
+        Ok(templates)
+    }
+
+    fn manifest(&self, template: &Template) -> KataManifest {
+        kata_manifest(&self.template_path(template))
+    }
+
+    fn fetch(&self, template: &Template, dest: &Path) -> Result<(), Error> {
+        let src = self.template_path(template);
+        if !src.is_dir() {
+            return Err(Error::KataNotFound {
+                kata: template.kata.clone(),
+                location: self.dir.join(&template.language).display().to_string(),
+            });
+        }
+        let options = CopyOptions {
+            copy_inside: true,
+            ..CopyOptions::new()
+        };
+        fs_extra::dir::copy(&src, dest, &options)
+            .map(|_| ())
+            .map_err(|e| {
+                Error::io(format!(
+                    "failed to copy {} to {}",
+                    src.display(),
+                    dest.display()
+                ))(std::io::Error::other(e.to_string()))
+            })
+    }
+}
+
+/// returns a temporary folder for a source or a template, unique within the process
+pub(crate) fn temp_source_dir(name: &str) -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "katac_source_{}_{}_{}",
+        name,
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
+/// a folder extracted or checked out to a temporary folder the first time it's needed, and
+/// removed when the source is dropped
+#[derive(Default)]
+struct Checkout {
+    dir: Mutex<Option<PathBuf>>,
+}
+
+impl Checkout {
+    /// returns the folder, creating it with `create` the first time
+    fn get(&self, create: impl FnOnce() -> Result<PathBuf, Error>) -> Result<DirSource, Error> {
+        let mut dir = self.dir.lock().unwrap_or_else(|e| e.into_inner());
+        if dir.is_none() {
+            *dir = Some(create()?);
+        }
+        Ok(DirSource::new(dir.clone().unwrap_or_default()))
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        if let Some(dir) = self.dir.get_mut().ok().and_then(|d| d.take()) {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// returns the name of an archive without its extension, None if it isn't a zip or tar archive
+fn archive_stem(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    [".tar.gz", ".tgz", ".tar", ".zip"]
+        .iter()
+        .find_map(|ext| name.strip_suffix(ext))
+}
+
+/// a zip or tar archive of example katas. The archive may also hold the examples folder
+/// itself, named like the archive, e.g. `tar czf examples.tar.gz examples`
+pub struct ArchiveSource {
+    archive: PathBuf,
+    checkout: Checkout,
+}
+
+impl ArchiveSource {
+    pub fn new(archive: impl Into<PathBuf>) -> Self {
+        ArchiveSource {
+            archive: archive.into(),
+            checkout: Checkout::default(),
+        }
+    }
+
+    /// returns the examples folder of the extracted archive
+    fn extracted(&self) -> Result<DirSource, Error> {
+        self.checkout
+            .get(|| {
+                let stem = archive_stem(&self.archive).unwrap_or("archive");
+                let dir = temp_source_dir(stem);
+                let ext = match self.archive.extension().and_then(|e| e.to_str()) {
+                    Some("zip") => "zip",
+                    _ => "tar",
+                };
+                fs::create_dir_all(&dir)
+                    .and_then(|_| extract_archive(&self.archive, &dir, ext))
+                    .map_err(|e| {
+                        let _ = fs::remove_dir_all(&dir);
+                        Error::io(format!("failed to extract {}", self.archive.display()))(e)
+                    })?;
+                Ok(dir)
+            })
+            .map(|source| {
+                let inner = source
+                    .dir
+                    .join(archive_stem(&self.archive).unwrap_or_default());
+                let only_entry = fs::read_dir(&source.dir).map(|e| e.count() == 1);
+                match inner.is_dir() && only_entry.unwrap_or(false) {
+                    true => DirSource::new(inner),
+                    false => source,
+                }
+            })
+    }
+}
+
+impl KataSource for ArchiveSource {
+    fn name(&self) -> String {
+        self.archive.display().to_string()
+    }
+
+    fn templates(&self) -> Result<Vec<Template>, Error> {
+        self.extracted()?.templates()
+    }
+
+    fn manifest(&self, template: &Template) -> KataManifest {
+        self.extracted()
+            .map(|source| source.manifest(template))
+            .unwrap_or_default()
+    }
+
+    fn fetch(&self, template: &Template, dest: &Path) -> Result<(), Error> {
+        self.extracted()?.fetch(template, dest)
+    }
+}
+
+/// a local git repository of example katas, its default branch is used
+pub struct GitSource {
+    repo: String,
+    checkout: Checkout,
+}
+
+impl GitSource {
+    pub fn new(repo: impl Into<String>) -> Self {
+        GitSource {
+            repo: repo.into(),
+            checkout: Checkout::default(),
+        }
+    }
+
+    /// returns the clone of the repository
+    fn cloned(&self) -> Result<DirSource, Error> {
+        self.checkout.get(|| {
+            let dir = temp_source_dir("git");
+            let output = Command::new("git")
+                // a repository named like an option isn't taken for one
+                .args(["clone", "--quiet", "--", &self.repo])
+                .arg(&dir)
+                .output()
+                .map_err(Error::io("failed to run git"))?;
+            if !output.status.success() {
+                let _ = fs::remove_dir_all(&dir);
+                return Err(Error::InvalidInput(format!(
+                    "failed to clone {}: {}",
+                    self.repo,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            Ok(dir)
+        })
+    }
+}
+
+impl KataSource for GitSource {
+    fn name(&self) -> String {
+        self.repo.clone()
+    }
+
+    fn templates(&self) -> Result<Vec<Template>, Error> {
+        self.cloned()?.templates()
+    }
+
+    fn manifest(&self, template: &Template) -> KataManifest {
+        self.cloned()
+            .map(|source| source.manifest(template))
+            .unwrap_or_default()
+    }
+
+    fn fetch(&self, template: &Template, dest: &Path) -> Result<(), Error> {
+        self.cloned()?.fetch(template, dest)?;
+        // the clone of a kata isn't a repository of its own
+        let _ = fs::remove_dir_all(dest.join(".git"));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// creates an examples folder with the given language/kata/file paths
+    fn examples_dir(name: &str, files: &[&str]) -> TempDir {
+        let files: Vec<(&str, &str)> = files.iter().map(|file| (*file, *file)).collect();
+        TempDir::with_files(&format!("sources_{}", name), &files)
+    }
+
+    fn template(language: &str, kata: &str) -> Template {
+        Template {
+            language: language.to_string(),
+            kata: kata.to_string(),
+        }
+    }
+
+    #[test]
+    fn opens_sources_by_spec() {
+        assert_eq!(open_source("embedded").name(), "embedded");
+        assert_eq!(open_source("examples").name(), "examples");
+        assert!(archive_stem(Path::new("katas.tar.gz")) == Some("katas"));
+        assert!(archive_stem(Path::new("katas.zip")) == Some("katas"));
+        assert!(archive_stem(Path::new("katas")).is_none());
+    }
+
+    #[test]
+    fn dir_source() {
+        let dir = examples_dir(
+            "dir",
+            &[
+                "go/LRU/lru.go",
+                "python/LRU/lru.py",
+                "go/Trie/trie.go",
+                ".git/HEAD",
+            ],
+        );
+        let source = DirSource::new(dir.to_path_buf());
+        assert_eq!(
+            source.templates().unwrap(),
+            [
+                template("go", "LRU"),
+                template("python", "LRU"),
+                template("go", "Trie")
+            ]
+        );
+        assert_eq!(source.find("LRU").unwrap(), Some(template("go", "LRU")));
+        assert_eq!(source.find("Heap").unwrap(), None);
+
+        let dest = dir.join("fetched");
+        source.fetch(&template("python", "LRU"), &dest).unwrap();
+        assert!(dest.join("lru.py").exists());
+        assert!(source.fetch(&template("go", "Heap"), &dest).is_err());
+    }
+
+    #[test]
+    fn embedded_source() {
+        let source = EmbeddedSource;
+        let templates = source.templates().unwrap();
+        assert!(!templates.is_empty());
+
+        let dir = TempDir::new("sources_embedded");
+        let dest = dir.join("fetched");
+        source.fetch(&templates[0], &dest).unwrap();
+        assert!(fs::read_dir(&dest).unwrap().count() > 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn archive_source_of_the_examples_folder() {
+        let dir = examples_dir("archive", &["examples/go/LRU/lru.go"]);
+        let status = Command::new("tar")
+            .arg("czf")
+            .arg(dir.join("examples.tar.gz"))
+            .arg("-C")
+            .arg(&*dir)
+            .arg("examples")
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let source = ArchiveSource::new(dir.join("examples.tar.gz"));
+        assert_eq!(source.templates().unwrap(), [template("go", "LRU")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn zip_archive_source() {
+        let dir = examples_dir("zip", &["go/LRU/lru.go", "rust/Heap/heap.rs"]);
+        let status = Command::new("zip")
+            .args(["-q", "-r", "katas.zip", "go", "rust"])
+            .current_dir(&*dir)
+            .status()
+            .expect("zip should be available on the test runner");
+        assert!(status.success());
+
+        let source = ArchiveSource::new(dir.join("katas.zip"));
+        assert_eq!(
+            source.templates().unwrap(),
+            [template("rust", "Heap"), template("go", "LRU")]
+        );
+        let dest = dir.join("fetched");
+        source.fetch(&template("rust", "Heap"), &dest).unwrap();
+        assert!(dest.join("heap.rs").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn git_source() {
+        let dir = examples_dir("git", &["go/LRU/lru.go", "go/Trie/trie.go"]);
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args([
+                    "-c",
+                    "user.name=katac",
+                    "-c",
+                    "user.email=katac@example.com",
+                ])
+                .args(["-c", "commit.gpgsign=false"])
+                .args(args)
+                .current_dir(&*dir)
+                .status()
+                .expect("git should be available on the test runner");
+            assert!(status.success());
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "katas"]);
+
+        let source = GitSource::new(dir.display().to_string());
+        assert_eq!(
+            source.templates().unwrap(),
+            [template("go", "LRU"), template("go", "Trie")]
+        );
+        let dest = dir.join("fetched");
+        source.fetch(&template("go", "Trie"), &dest).unwrap();
+        assert!(dest.join("trie.go").exists());
+        assert!(!dest.join(".git").exists());
+
+        // a repository named like an option of git clone is only a repository
+        let marker = dir.join("uploaded");
+        let spec = format!("--upload-pack=touch {}", marker.display());
+        let e = GitSource::new(spec.as_str()).templates().unwrap_err();
+        assert!(e
+            .to_string()
+            .ends_with(&format!("repository '{}' does not exist", spec)));
+        assert!(!marker.exists());
+    }
+}
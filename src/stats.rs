@@ -0,0 +1,428 @@
+//! Practice statistics computed from the history: how often and how well each kata was
+//! practiced, and totals per day and per language.
+
+use crate::{HistoryEvent, HistoryRecord};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+/// change of the latest time to green that counts as a trend, 10%
+const TREND_THRESHOLD: f64 = 0.1;
+
+/// records to include in the stats, every record by default
+#[derive(Debug, Clone, Default)]
+pub struct StatsFilter {
+    /// only these katas, all of them if empty
+    pub katas: Vec<String>,
+    /// only records from this date on
+    pub since: Option<NaiveDate>,
+    pub language: Option<String>,
+}
+
+impl StatsFilter {
+    fn matches(&self, record: &HistoryRecord) -> bool {
+        (self.katas.is_empty() || self.katas.contains(&record.kata))
+            && self
+                .since
+                .is_none_or(|since| record.timestamp.date_naive() >= since)
+            && self.language.as_ref().is_none_or(|language| {
+                record
+                    .language
+                    .as_ref()
+                    .is_some_and(|l| l.eq_ignore_ascii_case(language))
+            })
+    }
+}
+
+/// direction of a kata's time to green
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Trend {
+    Improving,
+    Steady,
+    Declining,
+}
+
+impl std::fmt::Display for Trend {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let trend = match self {
+            Trend::Improving => "improving",
+            Trend::Steady => "steady",
+            Trend::Declining => "declining",
+        };
+        write!(f, "{}", trend)
+    }
+}
+
+/// stats of a single kata
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct KataStats {
+    pub kata: String,
+    pub language: Option<String>,
+    /// number of days the kata was practiced in
+    pub practiced: usize,
+    pub last_practiced: NaiveDate,
+    pub runs: usize,
+    pub passed: usize,
+    /// share of the runs that passed, None if it was never run
+    pub pass_rate: Option<f64>,
+    /// time to green of the fastest, median and latest practice that got to green
+    #[serde(rename = "best_ms", serialize_with = "as_millis")]
+    pub best: Option<Duration>,
+    #[serde(rename = "median_ms", serialize_with = "as_millis")]
+    pub median: Option<Duration>,
+    #[serde(rename = "latest_ms", serialize_with = "as_millis")]
+    pub latest: Option<Duration>,
+    /// latest time to green compared to the median of the ones before it
+    pub trend: Option<Trend>,
+}
+
+/// totals of a single day
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DayStats {
+    pub day: u32,
+    /// date of the first record of the day
+    pub date: NaiveDate,
+    pub katas: usize,
+    pub runs: usize,
+    pub passed: usize,
+}
+
+/// totals of a single language
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LanguageStats {
+    pub language: String,
+    pub practiced: usize,
+    pub runs: usize,
+    pub passed: usize,
+    pub pass_rate: Option<f64>,
+}
+
+/// every stat of the history
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub katas: Vec<KataStats>,
+    pub days: Vec<DayStats>,
+    pub languages: Vec<LanguageStats>,
+}
+
+fn as_millis<S: serde::Serializer>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    match d {
+        Some(d) => s.serialize_some(&(d.as_millis() as u64)),
+        None => s.serialize_none(),
+    }
+}
+
+/// the records of a kata in one day, oldest first
+struct Practice<'a> {
+    kata: &'a str,
+    day: u32,
+    records: Vec<&'a HistoryRecord>,
+}
+
+impl Practice<'_> {
+    fn runs(&self) -> impl Iterator<Item = &&HistoryRecord> {
+        self.records.iter().filter(|r| r.event == HistoryEvent::Run)
+    }
+
+    fn start(&self) -> DateTime<FixedOffset> {
+        // run records are written when the run ends
+        let first = self.records[0];
+        first.timestamp - chrono::Duration::milliseconds(first.duration_ms.unwrap_or(0) as i64)
+    }
+
+    /// time from the start of the practice to the end of its first passing run
+    fn time_to_green(&self) -> Option<Duration> {
+        let green = self.runs().find(|r| r.passed == Some(true))?;
+        (green.timestamp - self.start()).to_std().ok()
+    }
+}
+
+/// computes the stats of the records that match the filter
+pub fn compute(records: &[HistoryRecord], filter: &StatsFilter) -> Stats {
+    let mut records: Vec<&HistoryRecord> = records.iter().filter(|r| filter.matches(r)).collect();
+    records.sort_by_key(|r| r.timestamp);
+
+    let mut practices: BTreeMap<(&str, u32), Practice> = BTreeMap::new();
+    for record in &records {
+        practices
+            .entry((&record.kata, record.day))
+            .or_insert_with(|| Practice {
+                kata: &record.kata,
+                day: record.day,
+                records: Vec::new(),
+            })
+            .records
+            .push(record);
+    }
+
+    Stats {
+        katas: kata_stats(&practices),
+        days: day_stats(&practices),
+        languages: language_stats(&records, &practices),
+    }
+}
+
+fn kata_stats(practices: &BTreeMap<(&str, u32), Practice>) -> Vec<KataStats> {
+    let mut by_kata: BTreeMap<&str, Vec<&Practice>> = BTreeMap::new();
+    for practice in practices.values() {
+        by_kata.entry(practice.kata).or_default().push(practice);
+    }
+
+    by_kata
+        .into_iter()
+        .map(|(kata, mut practices)| {
+            practices.sort_by_key(|p| p.start());
+            let records = || practices.iter().flat_map(|p| p.records.iter());
+            let runs = records().filter(|r| r.event == HistoryEvent::Run).count();
+            let passed = records().filter(|r| r.passed == Some(true)).count();
+            let times: Vec<Duration> = practices.iter().filter_map(|p| p.time_to_green()).collect();
+
+            KataStats {
+                kata: kata.to_string(),
+                language: records().rev().find_map(|r| r.language.clone()),
+                practiced: practices.len(),
+                last_practiced: records()
+                    .map(|r| r.timestamp.date_naive())
+                    .max()
+                    .unwrap_or_default(),
+                runs,
+                passed,
+                pass_rate: rate(passed, runs),
+                best: times.iter().min().copied(),
+                median: median(&times),
+                latest: times.last().copied(),
+                trend: trend(&times),
+            }
+        })
+        .collect()
+}
+
+fn day_stats(practices: &BTreeMap<(&str, u32), Practice>) -> Vec<DayStats> {
+    let mut by_day: BTreeMap<u32, Vec<&Practice>> = BTreeMap::new();
+    for practice in practices.values() {
+        by_day.entry(practice.day).or_default().push(practice);
+    }
+
+    by_day
+        .into_iter()
+        .map(|(day, practices)| {
+            let records = || practices.iter().flat_map(|p| p.records.iter());
+            DayStats {
+                day,
+                date: records()
+                    .map(|r| r.timestamp.date_naive())
+                    .min()
+                    .unwrap_or_default(),
+                katas: practices.len(),
+                runs: records().filter(|r| r.event == HistoryEvent::Run).count(),
+                passed: records().filter(|r| r.passed == Some(true)).count(),
+            }
+        })
+        .collect()
+}
+
+fn language_stats(
+    records: &[&HistoryRecord],
+    practices: &BTreeMap<(&str, u32), Practice>,
+) -> Vec<LanguageStats> {
+    let languages: BTreeSet<&str> = records
+        .iter()
+        .filter_map(|r| r.language.as_deref())
+        .collect();
+
+    languages
+        .into_iter()
+        .map(|language| {
+            let records = || {
+                records
+                    .iter()
+                    .filter(move |r| r.language.as_deref() == Some(language))
+            };
+            let runs = records().filter(|r| r.event == HistoryEvent::Run).count();
+            let passed = records().filter(|r| r.passed == Some(true)).count();
+            LanguageStats {
+                language: language.to_string(),
+                practiced: practices
+                    .values()
+                    .filter(|p| {
+                        p.records
+                            .iter()
+                            .any(|r| r.language.as_deref() == Some(language))
+                    })
+                    .count(),
+                runs,
+                passed,
+                pass_rate: rate(passed, runs),
+            }
+        })
+        .collect()
+}
+
+fn rate(passed: usize, total: usize) -> Option<f64> {
+    (total > 0).then(|| passed as f64 / total as f64)
+}
+
+fn median(times: &[Duration]) -> Option<Duration> {
+    let mut times = times.to_vec();
+    times.sort();
+    let mid = times.len() / 2;
+    match times.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((times[mid - 1] + times[mid]) / 2),
+        _ => Some(times[mid]),
+    }
+}
+
+/// compares the latest time with the median of the previous ones
+fn trend(times: &[Duration]) -> Option<Trend> {
+    let (latest, previous) = times.split_last()?;
+    let median = median(previous)?.as_secs_f64();
+    let change = (latest.as_secs_f64() - median) / median.max(f64::EPSILON);
+    Some(if change < -TREND_THRESHOLD {
+        Trend::Improving
+    } else if change > TREND_THRESHOLD {
+        Trend::Declining
+    } else {
+        Trend::Steady
+    })
+}
+
+/// formats a duration for humans, e.g. `4m12s` or `1h05m`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+/// formats a share as a percentage, `-` if there is none
+pub fn format_rate(rate: Option<f64>) -> String {
+    rate.map(|r| format!("{:.0}%", r * 100.0))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        time: &str,
+        event: HistoryEvent,
+        day: u32,
+        kata: &str,
+        passed: Option<bool>,
+    ) -> HistoryRecord {
+        HistoryRecord {
+            timestamp: DateTime::parse_from_rfc3339(time).unwrap(),
+            event,
+            day,
+            kata: kata.to_string(),
+            language: Some("go".to_string()),
+            passed,
+            duration_ms: passed.map(|_| 1000),
+        }
+    }
+
+    fn history() -> Vec<HistoryRecord> {
+        use HistoryEvent::*;
+        vec![
+            record("2024-03-01T09:00:00Z", Start, 1, "LRU", None),
+            record("2024-03-01T09:10:00Z", Run, 1, "LRU", Some(false)),
+            record("2024-03-01T09:20:00Z", Run, 1, "LRU", Some(true)),
+            record("2024-03-01T09:00:00Z", Start, 1, "Trie", None),
+            record("2024-03-03T18:00:00Z", Random, 2, "LRU", None),
+            record("2024-03-03T18:05:00Z", Run, 2, "LRU", Some(true)),
+            record("2024-03-05T08:00:00Z", Start, 3, "LRU", None),
+            record("2024-03-05T08:30:00Z", Run, 3, "LRU", Some(true)),
+        ]
+    }
+
+    #[test]
+    fn kata_stats() {
+        let stats = compute(&history(), &StatsFilter::default());
+        let lru = &stats.katas[0];
+        assert_eq!(lru.kata, "LRU");
+        assert_eq!(lru.practiced, 3);
+        assert_eq!(
+            lru.last_practiced,
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()
+        );
+        assert_eq!((lru.runs, lru.passed), (4, 3));
+        assert_eq!(lru.pass_rate, Some(0.75));
+        assert_eq!(lru.best, Some(Duration::from_secs(5 * 60)));
+        assert_eq!(lru.median, Some(Duration::from_secs(20 * 60)));
+        assert_eq!(lru.latest, Some(Duration::from_secs(30 * 60)));
+        assert_eq!(lru.trend, Some(Trend::Declining));
+
+        let trie = &stats.katas[1];
+        assert_eq!((trie.practiced, trie.runs), (1, 0));
+        assert_eq!((trie.pass_rate, trie.best, trie.trend), (None, None, None));
+    }
+
+    #[test]
+    fn day_and_language_totals() {
+        let stats = compute(&history(), &StatsFilter::default());
+        assert_eq!(
+            stats.days[0],
+            DayStats {
+                day: 1,
+                date: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+                katas: 2,
+                runs: 2,
+                passed: 1,
+            }
+        );
+        assert_eq!(stats.days.len(), 3);
+        assert_eq!(stats.languages.len(), 1);
+        assert_eq!(stats.languages[0].practiced, 4);
+        assert_eq!(stats.languages[0].pass_rate, Some(0.75));
+    }
+
+    #[test]
+    fn filters() {
+        let filter = StatsFilter {
+            since: NaiveDate::from_ymd_opt(2024, 3, 2),
+            ..StatsFilter::default()
+        };
+        let stats = compute(&history(), &filter);
+        assert_eq!(stats.katas.len(), 1);
+        assert_eq!(stats.katas[0].practiced, 2);
+
+        let filter = StatsFilter {
+            katas: vec!["Trie".to_string()],
+            ..StatsFilter::default()
+        };
+        assert_eq!(compute(&history(), &filter).katas[0].kata, "Trie");
+
+        let filter = StatsFilter {
+            language: Some("python".to_string()),
+            ..StatsFilter::default()
+        };
+        assert!(compute(&history(), &filter).katas.is_empty());
+    }
+
+    #[test]
+    fn trend_needs_two_times() {
+        let secs = |s: &[u64]| {
+            s.iter()
+                .map(|s| Duration::from_secs(*s))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(trend(&secs(&[60])), None);
+        assert_eq!(trend(&secs(&[60, 30])), Some(Trend::Improving));
+        assert_eq!(trend(&secs(&[60, 62])), Some(Trend::Steady));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(Duration::from_millis(12340)), "12.3s");
+        assert_eq!(format_duration(Duration::from_secs(252)), "4m12s");
+        assert_eq!(format_duration(Duration::from_secs(3900)), "1h05m");
+    }
+}
@@ -0,0 +1,250 @@
+//! Daily practice streaks and a GitHub style heatmap of the katas completed each day.
+
+use crate::{HistoryEvent, HistoryRecord};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// shades of the heatmap cells, by number of katas completed: 0, 1, 2, 3 and 4 or more
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// a run of consecutive days with at least one kata completed
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Streak {
+    pub days: u32,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+/// streaks of a history
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Streaks {
+    /// streak that ends today, or yesterday since today isn't over yet
+    pub current: Option<Streak>,
+    pub longest: Option<Streak>,
+}
+
+/// returns the number of katas completed each day. A kata is completed on the days it passed a
+/// run, or on the day it was started if `katac run` was never used on it, for katas run by hand
+pub fn completed_per_day(records: &[HistoryRecord]) -> BTreeMap<NaiveDate, usize> {
+    // (kata, day) of the practices that have runs
+    let run_practices: HashSet<(&str, u32)> = records
+        .iter()
+        .filter(|r| r.event == HistoryEvent::Run)
+        .map(|r| (r.kata.as_str(), r.day))
+        .collect();
+
+    let mut completed: HashMap<NaiveDate, HashSet<&str>> = HashMap::new();
+    for record in records {
+        let counts = match record.event {
+            HistoryEvent::Run => record.passed == Some(true),
+            HistoryEvent::Start | HistoryEvent::Random => {
+                !run_practices.contains(&(record.kata.as_str(), record.day))
+            }
+        };
+        if counts {
+            completed
+                .entry(record.timestamp.date_naive())
+                .or_default()
+                .insert(&record.kata);
+        }
+    }
+
+    completed
+        .into_iter()
+        .map(|(date, katas)| (date, katas.len()))
+        .collect()
+}
+
+/// finds the current and longest streaks of the days with completed katas
+pub fn streaks(completed: &BTreeMap<NaiveDate, usize>, today: NaiveDate) -> Streaks {
+    let mut all: Vec<Streak> = Vec::new();
+    for &date in completed.keys().filter(|d| completed[*d] > 0) {
+        match all.last_mut() {
+            Some(streak) if streak.end + Duration::days(1) == date => {
+                streak.end = date;
+                streak.days += 1;
+            }
+            _ => all.push(Streak {
+                days: 1,
+                start: date,
+                end: date,
+            }),
+        }
+    }
+
+    let current = all
+        .last()
+        .filter(|s| s.end == today || s.end + Duration::days(1) == today)
+        .copied();
+    // the earliest of the longest streaks
+    let longest = all.iter().rev().max_by_key(|s| s.days).copied();
+    Streaks { current, longest }
+}
+
+/// returns the shade of a heatmap cell
+fn shade(completed: usize) -> char {
+    SHADES[completed.min(SHADES.len() - 1)]
+}
+
+/// renders the last `weeks` weeks up to today as a heatmap, one column per week from monday
+/// to sunday, with the months on top
+pub fn heatmap(completed: &BTreeMap<NaiveDate, usize>, today: NaiveDate, weeks: u32) -> String {
+    let weeks = weeks.max(1);
+    let this_monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let first_monday = this_monday - Duration::weeks(weeks as i64 - 1);
+    let week_start = |week: u32| first_monday + Duration::weeks(week as i64);
+
+    const LABEL_WIDTH: usize = 5;
+    let mut out = String::new();
+
+    // a month label over the first week that has the 1st of the month, or over the first week
+    let mut months = vec![' '; weeks as usize * 2];
+    let mut free_from = 0;
+    for week in 0..weeks {
+        let start = week_start(week);
+        let has_first = (0..7).any(|d| (start + Duration::days(d)).day() == 1);
+        let col = week as usize * 2;
+        if (week == 0 || has_first) && col >= free_from {
+            let month = (start + Duration::days(6)).format("%b").to_string();
+            for (i, c) in month.chars().enumerate() {
+                if let Some(cell) = months.get_mut(col + i) {
+                    *cell = c;
+                }
+            }
+            free_from = col + month.len() + 1;
+        }
+    }
+    out.push_str(&" ".repeat(LABEL_WIDTH));
+    out.push_str(months.iter().collect::<String>().trim_end());
+    out.push('\n');
+
+    for (weekday, name) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+        .iter()
+        .enumerate()
+    {
+        let mut row = format!("{:<LABEL_WIDTH$}", name);
+        for week in 0..weeks {
+            let date = week_start(week) + Duration::days(weekday as i64);
+            let cell = if date > today {
+                ' '
+            } else {
+                shade(completed.get(&date).copied().unwrap_or(0))
+            };
+            row.push(cell);
+            row.push(' ');
+        }
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+
+    out.push_str(&format!(
+        "\n{}less {} more\n",
+        " ".repeat(LABEL_WIDTH),
+        SHADES
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn record(time: &str, event: HistoryEvent, day: u32, kata: &str) -> HistoryRecord {
+        HistoryRecord {
+            timestamp: DateTime::parse_from_rfc3339(time).unwrap(),
+            event,
+            day,
+            kata: kata.to_string(),
+            language: None,
+            passed: (event == HistoryEvent::Run).then_some(!kata.starts_with("fail")),
+            duration_ms: None,
+        }
+    }
+
+    fn counts(dates: &[(&str, usize)]) -> BTreeMap<NaiveDate, usize> {
+        dates.iter().map(|(d, n)| (date(d), *n)).collect()
+    }
+
+    #[test]
+    fn completed_katas() {
+        use HistoryEvent::*;
+        let records = [
+            // run by hand, counts on the day it was started
+            record("2024-03-01T09:00:00+01:00", Start, 1, "LRU"),
+            // counts when it passes
+            record("2024-03-01T09:00:00+01:00", Start, 1, "Trie"),
+            record("2024-03-02T09:00:00+01:00", Run, 1, "Trie"),
+            record("2024-03-02T09:30:00+01:00", Run, 1, "Trie"),
+            // never passed
+            record("2024-03-03T09:00:00+01:00", Random, 2, "fail_map"),
+            record("2024-03-03T09:10:00+01:00", Run, 2, "fail_map"),
+        ];
+        assert_eq!(
+            completed_per_day(&records),
+            counts(&[("2024-03-01", 1), ("2024-03-02", 1)])
+        );
+    }
+
+    #[test]
+    fn current_and_longest_streaks() {
+        let completed = counts(&[
+            ("2024-02-01", 1),
+            ("2024-02-02", 2),
+            ("2024-02-03", 1),
+            ("2024-02-10", 1),
+            ("2024-02-11", 1),
+        ]);
+
+        let streaks = streaks(&completed, date("2024-02-12"));
+        assert_eq!(
+            streaks.current,
+            Some(Streak {
+                days: 2,
+                start: date("2024-02-10"),
+                end: date("2024-02-11"),
+            })
+        );
+        assert_eq!(streaks.longest.map(|s| s.days), Some(3));
+        assert_eq!(streaks.longest.map(|s| s.start), Some(date("2024-02-01")));
+
+        let streaks = super::streaks(&completed, date("2024-02-13"));
+        assert_eq!(streaks.current, None);
+    }
+
+    #[test]
+    fn no_streaks() {
+        let streaks = streaks(&BTreeMap::new(), date("2024-02-13"));
+        assert_eq!(
+            streaks,
+            Streaks {
+                current: None,
+                longest: None
+            }
+        );
+    }
+
+    #[test]
+    fn heatmap_shades_and_months() {
+        // 2024-03-06 is a wednesday
+        let completed = counts(&[("2024-02-26", 1), ("2024-03-04", 2), ("2024-03-05", 5)]);
+        let map = heatmap(&completed, date("2024-03-06"), 2);
+        let lines: Vec<&str> = map.lines().collect();
+        assert_eq!(lines[0], "     Mar");
+        assert_eq!(lines[1], "Mon  ░ ▒");
+        assert_eq!(lines[2], "Tue  · █");
+        assert_eq!(lines[3], "Wed  · ·");
+        // days after today are left blank
+        assert_eq!(lines[4], "Thu  ·");
+        assert_eq!(lines[9], "     less · ░ ▒ ▓ █ more");
+    }
+}
@@ -0,0 +1,348 @@
+//! A katac workspace: the katas folder, the days folder, the config files and the practice
+//! history, resolved once and shared by every operation.
+
+use crate::config::{user_config_path, Layers, Origin};
+use crate::{
+    open_source, Args, CopyRecord, Data, DayMove, DayScheme, Days, EmbeddedSource, Error, History,
+    HistoryEvent, InitializedKata, KataDiff, KataSource, ListFilter, ListKind, Listing,
+    RandomOptions, RandomPicks, ResetResult, RunEvent, RunOptions, RunResult, Runner, Runners,
+    StartOptions, StatsFilter, StatsRecord, StreakRecord, Template, CONFIG_FILE_NAME, DAYS_DIR,
+    EMBEDDED_SOURCE, HISTORY_FILE, KATAS_DIR,
+};
+use chrono::Local;
+use std::path::{Path, PathBuf};
+
+/// where a workspace is, every folder left as None is resolved from the environment, the
+/// config file or the defaults
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceOptions {
+    /// folder the katas are copied from
+    pub katas_dir: Option<String>,
+    /// folder the katas are copied to every day
+    pub days_dir: Option<String>,
+    /// config file, ./katac.toml if None. It's fine if it doesn't exist
+    pub config: Option<String>,
+    /// user config, merged under the config file. $XDG_CONFIG_HOME/katac/config.toml if None,
+    /// it's fine if it doesn't exist
+    pub user_config: Option<PathBuf>,
+    /// template source of the katas that aren't in the katas folder, the one of the config if
+    /// None
+    pub source: Option<String>,
+}
+
+/// the folders, config and history katac works with
+#[derive(Debug)]
+pub struct Workspace {
+    pub(crate) katas_dir: String,
+    pub(crate) days: Days,
+    pub(crate) config: Data,
+    /// the config files the config was merged from, with where every value comes from
+    pub(crate) layers: Layers,
+    pub(crate) history: History,
+    pub(crate) runners: Runners,
+    /// where templates come from, embedded if not configured
+    pub(crate) source: Box<dyn KataSource>,
+    /// whether katas that aren't in the katas folder are copied from the source, only when it
+    /// was set in a config file or on the command line
+    pub(crate) source_fallback: bool,
+}
+
+impl Workspace {
+    /// resolves the workspace, reading the config files once
+    pub fn open(options: &WorkspaceOptions) -> Result<Workspace, Error> {
+        let project_config = PathBuf::from(options.config.as_deref().unwrap_or(CONFIG_FILE_NAME));
+        let mut files = Vec::new();
+        if let Some(user_config) = options.user_config.clone().or_else(user_config_path) {
+            files.push((user_config.clone(), Origin::User(user_config)));
+        }
+        files.push((project_config.clone(), Origin::Project(project_config)));
+        let mut layers = Layers::load(&files)?;
+
+        // priorities are: arg > env var > project config > user config > default value
+        let env_vars = [
+            ("katas_dir", "KATAS_DIR"),
+            ("days_dir", "DAYS_DIR"),
+            ("history_file", "KATAC_HISTORY"),
+        ];
+        for (key, env_var) in env_vars {
+            if let Ok(value) = std::env::var(env_var) {
+                layers.set("katas", key, value.into(), Origin::Env(env_var));
+            }
+        }
+        let args = [
+            ("katas_dir", "--katas-dir", &options.katas_dir),
+            ("days_dir", "--days-dir", &options.days_dir),
+            ("source", "--source", &options.source),
+        ];
+        for (key, arg, value) in args {
+            if let Some(value) = value {
+                layers.set("katas", key, value.clone().into(), Origin::Arg(arg));
+            }
+        }
+        let source_fallback = layers.is_set("katas.source");
+        layers.set_default("katas", "katas_dir", KATAS_DIR.into());
+        layers.set_default("katas", "days_dir", DAYS_DIR.into());
+        layers.set_default("katas", "history_file", HISTORY_FILE.into());
+        layers.set_default("katas", "day_scheme", "numbered".into());
+        layers.set_default("katas", "source", EMBEDDED_SOURCE.into());
+        layers.set_default("katas", "strategy", "uniform".into());
+        layers.set_default("katas", "start_today", false.into());
+        let config = layers.data()?;
+
+        let katas = &config.katas;
+        Ok(Workspace {
+            katas_dir: katas.katas_dir.clone().unwrap_or_default(),
+            days: Days::new(
+                katas.days_dir.clone().unwrap_or_default(),
+                katas.day_scheme.unwrap_or_default(),
+                Local::now().date_naive(),
+            ),
+            history: History::new(katas.history_file.clone().unwrap_or_default()),
+            runners: Runners::default(),
+            source: match &katas.source {
+                Some(spec) => open_source(spec),
+                None => Box::new(EmbeddedSource),
+            },
+            source_fallback,
+            config,
+            layers,
+        })
+    }
+
+    /// resolves the workspace given on the command line
+    pub fn from_args(args: &Args) -> Result<Workspace, Error> {
+        Workspace::open(&WorkspaceOptions {
+            katas_dir: args.katas_dir.clone(),
+            days_dir: args.days_dir.clone(),
+            config: args.config.clone(),
+            user_config: None,
+            source: args.source.clone(),
+        })
+    }
+
+    pub fn katas_dir(&self) -> &Path {
+        Path::new(&self.katas_dir)
+    }
+
+    pub fn days(&self) -> &Days {
+        &self.days
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn runners(&self) -> &Runners {
+        &self.runners
+    }
+
+    pub fn source(&self) -> &dyn KataSource {
+        self.source.as_ref()
+    }
+
+    /// the language preselected when picking templates, from the config
+    pub fn language(&self) -> Option<&str> {
+        self.config.katas.language.as_deref()
+    }
+
+    /// sets where templates come from when they aren't in the katas folder
+    pub fn set_source(&mut self, source: impl KataSource + 'static) {
+        self.source = Box::new(source);
+        self.source_fallback = true;
+    }
+
+    /// adds a runner that is tried before the built-in ones, replacing the runner with the
+    /// same name
+    pub fn register_runner(&mut self, runner: impl Runner + 'static) {
+        self.runners.register(Box::new(runner));
+    }
+
+    /// copies katas to a day, a new one by default, and records them in the history as started
+    pub fn start(
+        &self,
+        kata_names: &[String],
+        options: &StartOptions,
+    ) -> Result<Vec<CopyRecord>, Error> {
+        self.copy_katas(kata_names, HistoryEvent::Start, options)
+    }
+
+    /// copies katas to a day and records them in the history as the given event
+    pub fn copy_katas(
+        &self,
+        kata_names: &[String],
+        event: HistoryEvent,
+        options: &StartOptions,
+    ) -> Result<Vec<CopyRecord>, Error> {
+        crate::copy_katas(self, kata_names, event, options)
+    }
+
+    /// opens the katas at the given paths with the editor of the config, does nothing without
+    /// one
+    pub fn open_in_editor(&self, paths: &[PathBuf]) -> Result<(), Error> {
+        crate::open_in_editor(self, paths)
+    }
+
+    /// runs the given katas of the current day, or all of them, reporting what happens to
+    /// `on_event`
+    pub fn run(
+        &self,
+        kata_names: &Option<Vec<String>>,
+        options: &RunOptions,
+        on_event: &mut dyn FnMut(RunEvent),
+    ) -> Result<Vec<RunResult>, Error> {
+        crate::run_katas(self, kata_names, options, on_event)
+    }
+
+    /// lists the katas, the days or the templates of the given source, or the workspace's
+    pub fn list(
+        &self,
+        kind: ListKind,
+        filter: &ListFilter,
+        source: &Option<String>,
+    ) -> Result<Listing, Error> {
+        crate::list(self, kind, filter, source)
+    }
+
+    /// picks random katas, without copying them
+    pub fn random(
+        &self,
+        number_of_katas: u8,
+        options: &RandomOptions,
+    ) -> Result<RandomPicks, Error> {
+        crate::random_katas(self, number_of_katas, options)
+    }
+
+    /// creates a new kata in the katas folder, or at the given path, returns its path
+    pub fn new_kata(&self, kata_name: &str) -> Result<PathBuf, Error> {
+        crate::new_kata(self, kata_name)
+    }
+
+    /// restores a kata of the current day from its template, after backing up the attempt
+    pub fn reset(&self, kata_name: &str, files: &[PathBuf]) -> Result<ResetResult, Error> {
+        crate::reset_kata(self, kata_name, files)
+    }
+
+    /// returns the diff of a kata between two days, or its template and a day
+    pub fn diff(
+        &self,
+        kata_name: &str,
+        from: &Option<String>,
+        to: &Option<String>,
+    ) -> Result<KataDiff, Error> {
+        crate::diff_kata(self, kata_name, from, to)
+    }
+
+    /// returns the practice stats
+    pub fn stats(&self, filter: &StatsFilter) -> Result<StatsRecord, Error> {
+        crate::compute_stats(self, filter)
+    }
+
+    /// returns the practice streaks and a heatmap of the last `weeks` weeks
+    pub fn streak(&self, weeks: u32) -> Result<StreakRecord, Error> {
+        crate::compute_streak(self, weeks)
+    }
+
+    /// renames the dayN folders to a date based day scheme, returns the days moved
+    pub fn migrate_days(
+        &self,
+        to: Option<DayScheme>,
+        dry_run: bool,
+    ) -> Result<Vec<DayMove>, Error> {
+        crate::migrate_days(self, to, dry_run)
+    }
+
+    /// returns the source of the given spec, or the one of the workspace. A source opened from
+    /// the spec is kept in `opened`
+    pub fn template_source<'a>(
+        &'a self,
+        spec: &Option<String>,
+        opened: &'a mut Option<Box<dyn KataSource>>,
+    ) -> &'a dyn KataSource {
+        crate::template_source(self, spec, opened)
+    }
+
+    /// copies templates of a source to the katas folder
+    pub fn init(
+        &self,
+        source: &dyn KataSource,
+        templates: &[Template],
+    ) -> Result<Vec<InitializedKata>, Error> {
+        crate::init_katas(self, source, templates)
+    }
+
+    /// returns the effective value of every config key, with where it comes from
+    pub fn config_entries(&self) -> Vec<(&str, &toml::Value, &Origin)> {
+        self.layers.entries()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn resolves_config_and_arguments() {
+        let dir = TempDir::new("workspace");
+        let config = dir.join("katac.toml");
+        std::fs::write(
+            &config,
+            "[katas]\nkatas_dir = \"my_katas\"\ndays_dir = \"my_days\"\nday_scheme = \"date\"\n",
+        )
+        .unwrap();
+
+        let user_config = dir.join("config.toml");
+        std::fs::write(
+            &user_config,
+            "[katas]\nkatas_dir = \"user_katas\"\nhistory_file = \"user_history.jsonl\"\nlanguage = \"go\"\n",
+        )
+        .unwrap();
+
+        let workspace = Workspace::open(&WorkspaceOptions {
+            days_dir: Some("other_days".to_string()),
+            config: Some(config.display().to_string()),
+            user_config: Some(user_config.clone()),
+            ..WorkspaceOptions::default()
+        })
+        .unwrap();
+        assert_eq!(workspace.katas_dir(), Path::new("my_katas"));
+        assert_eq!(workspace.days().dir(), Path::new("other_days"));
+        assert_eq!(workspace.days().scheme(), DayScheme::Date);
+        assert_eq!(workspace.history().path(), dir.join("user_history.jsonl"));
+        assert_eq!(workspace.language(), Some("go"));
+
+        let origins: Vec<(&str, &Origin)> = workspace
+            .config_entries()
+            .into_iter()
+            .map(|(key, _, origin)| (key, origin))
+            .collect();
+        assert_eq!(
+            origins[..4],
+            [
+                ("katas.day_scheme", &Origin::Project(config.clone())),
+                ("katas.days_dir", &Origin::Arg("--days-dir")),
+                ("katas.history_file", &Origin::User(user_config)),
+                ("katas.katas_dir", &Origin::Project(config.clone())),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_config_uses_defaults() {
+        let workspace = Workspace::open(&WorkspaceOptions {
+            katas_dir: Some("k".to_string()),
+            days_dir: Some("d".to_string()),
+            config: Some("missing_katac.toml".to_string()),
+            user_config: Some(PathBuf::from("missing_config.toml")),
+            ..WorkspaceOptions::default()
+        })
+        .unwrap();
+        assert_eq!(workspace.katas_dir(), Path::new("k"));
+        assert_eq!(workspace.days().dir(), Path::new("d"));
+        assert_eq!(workspace.days().scheme(), DayScheme::Numbered);
+        assert_eq!(workspace.language(), None);
+        assert_eq!(workspace.source().name(), EMBEDDED_SOURCE);
+        // katas that are not in the katas folder are only copied from a configured source
+        assert!(!workspace.source_fallback);
+    }
+}
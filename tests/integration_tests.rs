@@ -1,5 +1,8 @@
 use assert_cmd::Command;
 use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 const DAY_FOLDER: &str = "tests/day_test";
 const PRG: &str = "katac";
 
@@ -9,10 +12,40 @@ fn cleanup(day_folder: &str) {
 
 type TestResult = Result<(), Box<dyn Error>>;
 
+/// folder of this run of the tests, under the target folder
+fn run_dir() -> &'static Path {
+    static RUN_DIR: OnceLock<PathBuf> = OnceLock::new();
+    RUN_DIR.get_or_init(|| {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!(
+            "katac-{}-{}",
+            std::process::id(),
+            nanos
+        ))
+    })
+}
+
+/// the katac binary with a history and a user config folder of its own for the running test, so
+/// tests don't read each other's practice nor the config or history of the user running them
+fn katac() -> Command {
+    let test = std::thread::current()
+        .name()
+        .unwrap_or("main")
+        .replace("::", "_");
+    let test_dir = run_dir().join(test);
+    let mut cmd = Command::cargo_bin(PRG).unwrap();
+    cmd.env("KATAC_HISTORY", test_dir.join("history.jsonl"))
+        .env("XDG_CONFIG_HOME", test_dir.join("xdg"));
+    cmd
+}
+
 #[test]
 fn test_copy_kata() -> TestResult {
     let test_day_folder = format!("{}_copy", DAY_FOLDER);
-    Command::cargo_bin(PRG)?
+    katac()
         .args([
             "foo",
             "--days-dir",
@@ -29,14 +62,14 @@ fn test_copy_kata() -> TestResult {
 #[test]
 fn test_run_kata() -> TestResult {
     let test_day_folder = format!("{}_run", DAY_FOLDER);
-    Command::cargo_bin(PRG)?
+    katac()
         .args(["baz"])
         .env("KATAS_DIR", "tests/example_katas")
         .env("DAYS_DIR", &test_day_folder)
         .assert()
         .stdout("Copying baz to day1...\n");
 
-    let cmd = Command::cargo_bin(PRG)?
+    let cmd = katac()
         .args(["run", "baz"])
         .env("DAYS_DIR", &test_day_folder)
         .assert();
@@ -52,7 +85,7 @@ fn test_run_kata() -> TestResult {
 #[test]
 fn test_multiple_kata() -> TestResult {
     let test_day_folder = format!("{}_multiple", DAY_FOLDER);
-    Command::cargo_bin(PRG)?
+    katac()
         .args([
             "--days-dir",
             &test_day_folder,
@@ -82,7 +115,7 @@ random = ["foo", "bar", "baz"]
     let test_day_folder = format!("{}_random_with_config", DAY_FOLDER);
     let katas = ["foo", "bar", "baz"];
     for _ in 0..5 {
-        Command::cargo_bin(PRG)?
+        katac()
             .args([
                 "--days-dir",
                 &test_day_folder,
@@ -117,7 +150,7 @@ fn test_random_no_config_file() -> TestResult {
 
     let katas = ["foo", "bar", "baz"];
     for _ in 0..5 {
-        Command::cargo_bin(PRG)?
+        katac()
             .args([
                 "--days-dir",
                 &test_day_folder,
@@ -148,21 +181,21 @@ fn test_random_no_config_file() -> TestResult {
 #[test]
 fn test_run_kata_no_makefile() -> TestResult {
     let test_day_folder = format!("{}_run_no_makefile", DAY_FOLDER);
-    Command::cargo_bin(PRG)?
+    katac()
         .args(["foo"])
         .env("KATAS_DIR", "tests/example_katas")
         .env("DAYS_DIR", &test_day_folder)
         .assert()
         .stdout("Copying foo to day1...\n");
 
-    let cmd = Command::cargo_bin(PRG)?
+    let cmd = katac()
         .args(["run", "foo"])
         .env("DAYS_DIR", &test_day_folder)
         .assert();
 
     let output = String::from_utf8(cmd.get_output().stdout.clone())?;
     assert!(output.contains("> Running foo [1/1]"));
-    assert!(output.contains("No Makefile found"));
+    assert!(output.contains("Nothing to run in"));
     assert!(output.contains(&test_day_folder));
     assert!(output.contains("foo"));
 
@@ -173,7 +206,7 @@ fn test_run_kata_no_makefile() -> TestResult {
 #[test]
 fn test_run_all() -> TestResult {
     let test_day_folder = format!("{}_run_all", DAY_FOLDER);
-    let cmd = Command::cargo_bin(PRG)?
+    let cmd = katac()
         .args(["foo", "bar", "baz"])
         .env("KATAS_DIR", "tests/example_katas")
         .env("DAYS_DIR", &test_day_folder)
@@ -184,7 +217,7 @@ fn test_run_all() -> TestResult {
         assert!(copy_output.contains(&format!("Copying {} to day1...", s)));
     }
 
-    let cmd = Command::cargo_bin(PRG)?
+    let cmd = katac()
         .args(["run"])
         .env("DAYS_DIR", &test_day_folder)
         .assert();
@@ -193,7 +226,7 @@ fn test_run_all() -> TestResult {
         assert!(run_output.contains(&format!("> Running {}", s)));
         match s {
             &"foo" | &"bar" => {
-                assert!(run_output.contains("No Makefile found"));
+                assert!(run_output.contains("Nothing to run in"));
                 assert!(run_output.contains(&test_day_folder));
             }
             &"baz" => {
@@ -206,10 +239,1202 @@ fn test_run_all() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn test_run_summary_and_exit_code() -> TestResult {
+    let test_day_folder = format!("{}_run_summary", DAY_FOLDER);
+    katac()
+        .args(["foo", "baz"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+
+    // foo has nothing to run, so it is skipped and the run still succeeds
+    let cmd = katac()
+        .args(["run", "foo", "baz"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("Summary"));
+    assert!(output.contains("skipped"));
+    assert!(output.contains("passed"));
+    assert!(output.contains("1 passed, 0 failed, 1 skipped"));
+
+    // a failing command makes katac exit non-zero
+    let cmd = katac()
+        .args(["run", "baz", "-c", "ls does-not-exist"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(1);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("0 passed, 1 failed, 0 skipped"));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_run_parallel_jobs() -> TestResult {
+    let test_day_folder = format!("{}_run_jobs", DAY_FOLDER);
+    katac()
+        .args(["foo", "bar", "baz"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+
+    let cmd = katac()
+        .args(["run", "foo", "bar", "baz", "--jobs", "3"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+
+    // outputs are buffered and printed in order, each under its own header
+    let foo = output.find("> Running foo [1/3]").unwrap();
+    let bar = output.find("> Running bar [2/3]").unwrap();
+    let baz = output.find("> Running baz [3/3]").unwrap();
+    let hello = output.find("console.log(\"hello world\")").unwrap();
+    assert!(foo < bar && bar < baz && baz < hello);
+    assert!(output.contains("1 passed, 0 failed, 2 skipped"));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_timeout() -> TestResult {
+    let test_day_folder = format!("{}_run_timeout", DAY_FOLDER);
+    katac()
+        .args(["baz"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+
+    let start = std::time::Instant::now();
+    let cmd = katac()
+        .args(["run", "baz", "-c", "sleep 30", "--timeout", "1"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(1);
+    assert!(start.elapsed() < std::time::Duration::from_secs(20));
+
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("Timed out after 1s"));
+    assert!(output.contains("timed out"));
+
+    // what the kata started is killed along with it
+    let kata_dir = format!("{}/day1/baz", test_day_folder);
+    let cmd = katac()
+        .args([
+            "run",
+            "baz",
+            "-c",
+            "sh -c '(sleep 2; echo x > survived) & wait'",
+            "--timeout",
+            "1",
+        ])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(1);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("Timed out after 1s"));
+    std::thread::sleep(std::time::Duration::from_secs(2));
+    assert!(
+        !Path::new(&kata_dir).join("survived").exists(),
+        "the subshell started by the kata is still running"
+    );
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_timeout_from_config_file() -> TestResult {
+    let test_day_folder = format!("{}_run_timeout_config", DAY_FOLDER);
+    let config_file = "tests/katac_timeout.toml";
+    std::fs::write(config_file, "[kata.baz]\ntimeout = 1\n")?;
+
+    katac()
+        .args(["baz"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+
+    let cmd = katac()
+        .args(["--config", config_file, "run", "baz", "-c", "sleep 30"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(1);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("Timed out after 1s"));
+
+    std::fs::remove_file(config_file)?;
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_command_quoting_and_shell() -> TestResult {
+    let test_day_folder = format!("{}_run_quoting", DAY_FOLDER);
+    katac()
+        .args(["baz"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+
+    // quotes and leading variable assignments are handled without a shell
+    let cmd = katac()
+        .args([
+            "run",
+            "baz",
+            "-c",
+            "GREETING=hi sh -c 'echo \"$GREETING world\"'",
+        ])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("hi world"));
+
+    // shell syntax is rejected unless --shell is given
+    let cmd = katac()
+        .args(["run", "baz", "-c", "echo one && echo two"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(1);
+    let stderr = String::from_utf8(cmd.get_output().stderr.clone())?;
+    assert!(stderr.contains("--shell"));
+
+    let cmd = katac()
+        .args(["run", "baz", "--shell", "-c", "echo one && echo two"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("one\ntwo"));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_parses_test_results() -> TestResult {
+    let test_day_folder = format!("{}_run_parser", DAY_FOLDER);
+    katac()
+        .args(["baz"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+
+    let tap = "printf 'TAP version 13\\nok 1 - adds\\nnot ok 2 - removes\\n1..2\\n'";
+    let cmd = katac()
+        .args(["run", "baz", "--parser", "tap", "--shell", "-c", tap])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("not ok 2 - removes"));
+    assert!(output.contains("baz: 1/2 tests passed"));
+    assert!(output.contains("FAIL removes"));
+
+    let cmd = katac()
+        .args([
+            "--format", "json", "run", "baz", "-p", "tap", "--shell", "-c", tap,
+        ])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let records: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    assert_eq!(records[0]["tests"]["total"], 2);
+    assert_eq!(records[0]["tests"]["failures"][0], "removes");
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_junit_report() -> TestResult {
+    let test_day_folder = format!("{}_run_junit", DAY_FOLDER);
+    katac()
+        .args(["foo", "bar"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+
+    let report = format!("{}/report.xml", test_day_folder);
+    katac()
+        .args(["run", "foo", "--junit", &report, "-c", "echo 'a < b'"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let xml = std::fs::read_to_string(&report)?;
+    assert!(xml.contains("<testsuite name=\"foo\" tests=\"1\" failures=\"0\""));
+    assert!(xml.contains("<system-out>a &lt; b\n</system-out>"));
+
+    katac()
+        .args(["run", "--junit", &report, "-c", "false"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(1);
+    let xml = std::fs::read_to_string(&report)?;
+    assert!(xml.contains("<testsuites name=\"katac\" tests=\"2\" failures=\"2\""));
+    assert!(xml.contains("<failure message=\"failed (1)\"/>"));
+
+    // a report that can't be written fails the run, after its summary
+    let report = format!("{}/day1/foo/main.go/report.xml", test_day_folder);
+    let cmd = katac()
+        .args(["run", "foo", "--junit", &report, "-c", "true"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(1);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("1 passed, 0 failed"));
+    let stderr = String::from_utf8(cmd.get_output().stderr.clone())?;
+    assert!(stderr.starts_with(&format!(
+        "Error: failed to write the JUnit report to {}",
+        report
+    )));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_history_records_start_and_run() -> TestResult {
+    let test_day_folder = format!("{}_history", DAY_FOLDER);
+    let history_file = format!("{}/.katac/history.jsonl", test_day_folder);
+    katac()
+        .args(["start", "foo", "bar"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .code(0);
+
+    katac()
+        .args(["run", "foo", "-c", "true"])
+        .env("DAYS_DIR", &test_day_folder)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .code(0);
+    katac()
+        .args(["run", "bar", "-c", "false"])
+        .env("DAYS_DIR", &test_day_folder)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .code(1);
+
+    let records: Vec<serde_json::Value> = std::fs::read_to_string(&history_file)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let summary: Vec<(&str, &str, u64, Option<bool>)> = records
+        .iter()
+        .map(|r| {
+            (
+                r["event"].as_str().unwrap(),
+                r["kata"].as_str().unwrap(),
+                r["day"].as_u64().unwrap(),
+                r["passed"].as_bool(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("start", "foo", 1, None),
+            ("start", "bar", 1, None),
+            ("run", "foo", 1, Some(true)),
+            ("run", "bar", 1, Some(false)),
+        ]
+    );
+    assert_eq!(records[0]["language"], "go");
+    assert!(records[3]["duration_ms"].is_u64());
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stats() -> TestResult {
+    let test_day_folder = format!("{}_stats", DAY_FOLDER);
+    let history_file = format!("{}/.katac/history.jsonl", test_day_folder);
+    let run_katac = |args: &[&str]| -> Result<Command, Box<dyn Error>> {
+        let mut cmd = katac();
+        cmd.args(args)
+            .env("KATAS_DIR", "tests/example_katas")
+            .env("DAYS_DIR", &test_day_folder)
+            .env("KATAC_HISTORY", &history_file);
+        Ok(cmd)
+    };
+
+    // without a history the days folder is scanned
+    run_katac(&["foo", "bar"])?.assert().code(0);
+    std::fs::remove_file(&history_file)?;
+    let cmd = run_katac(&["stats"])?.assert().code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("No history yet"));
+    assert!(output.contains("day1"));
+
+    run_katac(&["run", "foo", "-c", "false"])?.assert().code(1);
+    run_katac(&["run", "foo", "-c", "true"])?.assert().code(0);
+    let cmd = run_katac(&["--format", "json", "stats", "--kata", "foo"])?
+        .assert()
+        .code(0);
+    let stats: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    assert_eq!(stats["source"], "history");
+    assert_eq!(stats["katas"].as_array().map(|k| k.len()), Some(1));
+    assert_eq!(stats["katas"][0]["kata"], "foo");
+    assert_eq!(stats["katas"][0]["runs"], 2);
+    assert_eq!(stats["katas"][0]["pass_rate"], 0.5);
+    assert_eq!(stats["languages"][0]["language"], "go");
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_streak() -> TestResult {
+    let test_day_folder = format!("{}_streak", DAY_FOLDER);
+    let history_file = format!("{}/history.jsonl", test_day_folder);
+    std::fs::create_dir_all(&test_day_folder)?;
+    let records: Vec<String> = ["2024-02-01", "2024-02-02", "2024-02-03", "2024-02-10"]
+        .iter()
+        .enumerate()
+        .map(|(i, date)| {
+            format!(
+                r#"{{"timestamp":"{}T09:00:00+01:00","event":"start","day":{},"kata":"foo"}}"#,
+                date,
+                i + 1
+            )
+        })
+        .collect();
+    std::fs::write(&history_file, records.join("\n"))?;
+
+    let cmd = katac()
+        .args(["streak", "--weeks", "4"])
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("Current streak: 0 days"));
+    assert!(output.contains("Longest streak: 3 day(s), 2024-02-01 to 2024-02-03"));
+    assert!(output.contains("Mon  "));
+
+    let cmd = katac()
+        .args(["--format", "json", "streak"])
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .code(0);
+    let streak: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    assert_eq!(streak["longest_streak"]["days"], 3);
+    assert!(streak["current_streak"].is_null());
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_random_seed() -> TestResult {
+    let random = |day_folder: &str, args: &[&str]| -> Result<String, Box<dyn Error>> {
+        let cmd = katac()
+            .args(["random", "2"])
+            .args(args)
+            .env("KATAS_DIR", "tests/example_katas")
+            .env("DAYS_DIR", day_folder)
+            .env("KATAC_HISTORY", format!("{}/history.jsonl", day_folder))
+            .assert()
+            .code(0);
+        let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+        cleanup(day_folder);
+        Ok(output)
+    };
+
+    let first = random(&format!("{}_seed_1", DAY_FOLDER), &["--seed", "7"])?;
+    let second = random(&format!("{}_seed_2", DAY_FOLDER), &["--seed", "7"])?;
+    assert!(first.starts_with("Seed: 7 "));
+    assert_eq!(first, second);
+
+    let from_date = random(&format!("{}_seed_date", DAY_FOLDER), &["--seed-from-date"])?;
+    let seed = from_date
+        .strip_prefix("Seed: ")
+        .and_then(|s| s.split_whitespace().next())
+        .unwrap_or_default();
+    assert_eq!(seed.len(), 8);
+    assert!(seed.starts_with("20"));
+
+    katac()
+        .args(["random", "2", "--seed", "7", "--seed-from-date"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn test_random_spaced_strategy() -> TestResult {
+    let test_day_folder = format!("{}_random_spaced", DAY_FOLDER);
+    let history_file = format!("{}/history.jsonl", test_day_folder);
+    std::fs::create_dir_all(&test_day_folder)?;
+    std::fs::write(
+        &history_file,
+        r#"{"timestamp":"2024-01-01T09:00:00+01:00","event":"run","day":1,"kata":"baz","passed":false}"#,
+    )?;
+
+    let cmd = katac()
+        .args(["random", "1", "--strategy", "spaced"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("Picked by the spaced repetition schedule:"));
+    assert!(output.contains("baz  overdue by"));
+    assert!(output.contains("failed on 2024-01-01"));
+    assert!(output.contains("Copying baz to day1..."));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_random_pools() -> TestResult {
+    let test_day_folder = format!("{}_random_pools", DAY_FOLDER);
+    let config_file = format!("{}/katac.toml", test_day_folder);
+    std::fs::create_dir_all(&test_day_folder)?;
+    std::fs::write(
+        &config_file,
+        r#"
+[kata.bar]
+tags = ["ocaml"]
+
+[pools.hard]
+katas = ["foo", "bar", { name = "baz", weight = 0 }]
+require = [{ tag = "ocaml" }]
+
+[pools.tags]
+require = [{ tag = "ocaml", at_least = 2 }]
+"#,
+    )?;
+    let random = |args: &[&str]| {
+        katac()
+            .args(["--config", &config_file, "random"])
+            .args(args)
+            .env("KATAS_DIR", "tests/example_katas")
+            .env("DAYS_DIR", &test_day_folder)
+            .env(
+                "KATAC_HISTORY",
+                format!("{}/history.jsonl", test_day_folder),
+            )
+            .assert()
+    };
+
+    // bar is required, baz never picked with a weight of 0
+    for _ in 0..5 {
+        random(&["2", "--pool", "hard"]).code(0);
+        let day_folder = std::path::Path::new(&test_day_folder).join("day1");
+        let mut katas: Vec<String> = day_folder
+            .read_dir()?
+            .map(|f| f.unwrap().file_name().into_string().unwrap())
+            .collect();
+        katas.sort();
+        assert_eq!(katas, ["bar", "foo"]);
+        std::fs::remove_dir_all(day_folder)?;
+    }
+
+    random(&["1", "--pool", "tags"]).failure().stderr(
+        "Error: pool 'tags' needs at least 2 kata(s) tagged 'ocaml' but only 1 can be picked\n",
+    );
+    random(&["1", "--pool", "easy"])
+        .failure()
+        .stderr("Error: pool 'easy' not found, the pools are: hard, tags\n");
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_kata_manifest() -> TestResult {
+    let test_day_folder = format!("{}_manifest", DAY_FOLDER);
+    let katas_dir = format!("{}/katas", test_day_folder);
+    let kata_dir = format!("{}/lru", katas_dir);
+    std::fs::create_dir_all(format!("{}/solution", kata_dir))?;
+    std::fs::write(format!("{}/solution/lru.js", kata_dir), "// solved")?;
+    std::fs::write(
+        format!("{}/Makefile", kata_dir),
+        "run:\n\t@echo from the makefile\n",
+    )?;
+    std::fs::write(
+        format!("{}/kata.toml", kata_dir),
+        r#"
+description = "An LRU cache"
+difficulty = "medium"
+estimated_minutes = 20
+tags = ["cache"]
+language = "javascript"
+run = "echo from kata.toml"
+solution = "solution"
+"#,
+    )?;
+    let days_dir = format!("{}/days", test_day_folder);
+    let history_file = format!("{}/history.jsonl", test_day_folder);
+
+    katac()
+        .args(["lru"])
+        .env("KATAS_DIR", &katas_dir)
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .stdout("Copying lru to day1...\n  An LRU cache (medium, ~20 min)\n");
+    // the solution stays in the katas folder
+    assert!(!std::path::Path::new(&format!("{}/day1/lru/solution", days_dir)).exists());
+    assert!(std::fs::read_to_string(&history_file)?.contains(r#""language":"javascript""#));
+    // and isn't a change of the kata
+    katac()
+        .args(["diff", "lru"])
+        .env("KATAS_DIR", &katas_dir)
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .code(0)
+        .stdout("No changes in lru\n");
+
+    // the run command of kata.toml is used instead of the Makefile
+    let cmd = katac()
+        .args(["run", "lru"])
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("Running kata.toml command: echo from kata.toml"));
+    assert!(output.contains("from kata.toml\n"));
+    assert!(!output.contains("from the makefile"));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_list() -> TestResult {
+    let test_day_folder = format!("{}_list", DAY_FOLDER);
+    let history_file = format!("{}/history.jsonl", test_day_folder);
+    let run_katac = |args: &[&str]| {
+        katac()
+            .args(args)
+            .env("KATAS_DIR", "tests/example_katas")
+            .env("DAYS_DIR", &test_day_folder)
+            .env("KATAC_HISTORY", &history_file)
+            .assert()
+            .code(0)
+    };
+    run_katac(&["foo", "baz"]);
+    run_katac(&["run", "baz"]);
+
+    let cmd = run_katac(&["list", "katas", "--format", "json"]);
+    let katas: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    let katas = katas.as_array().unwrap();
+    assert_eq!(katas.len(), 3);
+    assert_eq!(katas[0]["kata"], "bar");
+    assert_eq!(katas[0]["last_practiced"], serde_json::Value::Null);
+    assert_eq!(katas[2]["kata"], "foo");
+    assert_eq!(katas[2]["language"], "go");
+    assert_eq!(katas[2]["times_practiced"], 1);
+
+    let cmd = run_katac(&["list", "days"]);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("day1  baz   javascript  passed"));
+    assert!(output.contains("      foo   go          not run"));
+
+    let cmd = run_katac(&["list", "days", "--language", "go", "--format", "json"]);
+    let days: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    assert_eq!(days[0]["katas"].as_array().unwrap().len(), 1);
+    assert_eq!(days[0]["katas"][0]["kata"], "foo");
+
+    let cmd = run_katac(&["list", "templates", "--language", "python"]);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.starts_with("python ("));
+    assert!(output.contains("  LRU"));
+    assert!(!output.contains("go ("));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_start_today_and_day() -> TestResult {
+    let test_day_folder = format!("{}_start_today", DAY_FOLDER);
+    let start = |args: &[&str]| {
+        katac()
+            .arg("start")
+            .args(args)
+            .env("KATAS_DIR", "tests/example_katas")
+            .env("DAYS_DIR", &test_day_folder)
+            .env(
+                "KATAC_HISTORY",
+                format!("{}/history.jsonl", test_day_folder),
+            )
+            .assert()
+    };
+
+    // without days yet, today is day1
+    start(&["foo", "--today"])
+        .code(0)
+        .stdout("Copying foo to day1...\n");
+    start(&["bar", "--today"])
+        .code(0)
+        .stdout("Copying bar to day1...\n");
+    let cmd = start(&["foo", "--today"]).failure();
+    let stderr = String::from_utf8(cmd.get_output().stderr.clone())?;
+    assert!(stderr.contains("Error: 'foo' is already in day1, pass --force to replace it"));
+    std::fs::write(format!("{}/day1/foo/main.go", test_day_folder), "changed")?;
+    start(&["foo", "--today", "--force"]).code(0);
+    assert_ne!(
+        std::fs::read_to_string(format!("{}/day1/foo/main.go", test_day_folder))?,
+        "changed"
+    );
+
+    start(&["baz"]).code(0).stdout("Copying baz to day2...\n");
+    start(&["baz", "--day", "1"])
+        .code(0)
+        .stdout("Copying baz to day1...\n");
+    start(&["foo", "--today", "--day", "1"]).failure();
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_date_day_folders() -> TestResult {
+    let test_day_folder = format!("{}_date_days", DAY_FOLDER);
+    let days_dir = format!("{}/days", test_day_folder);
+    let history_file = format!("{}/history.jsonl", test_day_folder);
+    let config_file = format!("{}/katac.toml", test_day_folder);
+    let run_katac = |args: &[&str]| {
+        katac()
+            .args(["--config", &config_file])
+            .args(args)
+            .env("KATAS_DIR", "tests/example_katas")
+            .env("DAYS_DIR", &days_dir)
+            .env("KATAC_HISTORY", &history_file)
+            .assert()
+    };
+    run_katac(&["foo"]).code(0);
+    run_katac(&["bar"]).code(0);
+    // noon UTC, the same date in every time zone
+    for (day, secs) in [("day1", 1709294400), ("day2", 1709380800)] {
+        std::fs::File::open(format!("{}/{}", days_dir, day))?
+            .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))?;
+    }
+
+    run_katac(&["migrate-days"]).failure();
+    run_katac(&["migrate-days", "--to", "date", "--dry-run"])
+        .code(0)
+        .stdout("day1 -> 2024-03-01\nday2 -> 2024-03-02\n");
+    run_katac(&["migrate-days", "--to", "week", "--dry-run"])
+        .code(0)
+        .stdout("day1 -> 2024-W09/fri\nday2 -> 2024-W09/sat\n");
+    assert!(std::path::Path::new(&format!("{}/day1", days_dir)).exists());
+
+    std::fs::write(&config_file, "[katas]\nday_scheme = \"date\"\n")?;
+    run_katac(&["migrate-days"]).code(0);
+    assert!(std::path::Path::new(&format!("{}/2024-03-01/foo", days_dir)).exists());
+    assert!(std::path::Path::new(&format!("{}/2024-03-02/bar", days_dir)).exists());
+    assert!(!std::path::Path::new(&format!("{}/day1", days_dir)).exists());
+    let history = std::fs::read_to_string(&history_file)?;
+    assert!(history.contains(r#""day":20240301,"kata":"foo""#));
+    assert!(history.contains(r#""day":20240302,"kata":"bar""#));
+
+    let cmd = run_katac(&["start", "baz", "--day", "2024-03-01", "--format", "json"]).code(0);
+    let copied: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    assert_eq!(copied[0]["day"], 20240301);
+    assert_eq!(copied[0]["day_name"], "2024-03-01");
+    let cmd = run_katac(&["run", "bar", "-c", "true", "--format", "json"]).code(0);
+    let records: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    assert_eq!(records[0]["day_name"], "2024-03-02");
+    let cmd = run_katac(&["list", "days", "--format", "json"]).code(0);
+    let days: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    assert_eq!(days[0]["name"], "2024-03-01");
+    assert_eq!(days[0]["katas"].as_array().unwrap().len(), 2);
+    assert_eq!(days[1]["name"], "2024-03-02");
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_reset() -> TestResult {
+    let test_day_folder = format!("{}_reset", DAY_FOLDER);
+    let days_dir = format!("{}/days", test_day_folder);
+    let index_js = format!("{}/day1/baz/index.js", days_dir);
+    let makefile = format!("{}/day1/baz/Makefile", days_dir);
+    std::fs::create_dir_all(&test_day_folder)?;
+    // katac runs from the test folder, the trash is in its .katac folder
+    let run_katac = |args: &[&str]| {
+        katac()
+            .args(args)
+            .current_dir(&test_day_folder)
+            .env(
+                "KATAS_DIR",
+                format!("{}/tests/example_katas", env!("CARGO_MANIFEST_DIR")),
+            )
+            .env("DAYS_DIR", "days")
+            .env("KATAC_HISTORY", "history.jsonl")
+            .assert()
+    };
+    run_katac(&["reset", "baz"]).failure();
+    run_katac(&["start", "baz"]).code(0);
+    let template = std::fs::read_to_string("tests/example_katas/baz/index.js")?;
+    std::fs::write(&index_js, "my attempt")?;
+    std::fs::write(&makefile, "my makefile")?;
+
+    let cmd = run_katac(&["reset", "baz", "--files", "index.js"]).code(0);
+    assert!(String::from_utf8(cmd.get_output().stdout.clone())?
+        .ends_with("Reset index.js of baz in day1\n"));
+    assert_eq!(std::fs::read_to_string(&index_js)?, template);
+    assert_eq!(std::fs::read_to_string(&makefile)?, "my makefile");
+
+    run_katac(&["reset", "baz"]).code(0);
+    assert_ne!(std::fs::read_to_string(&makefile)?, "my makefile");
+
+    // both attempts are in the trash
+    let backups: Vec<_> = std::fs::read_dir(format!("{}/.katac/trash", test_day_folder))?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(backups.len(), 2);
+    assert!(backups.iter().any(
+        |b| std::fs::read_to_string(b.join("index.js")).ok().as_deref() == Some("my attempt")
+    ));
+
+    run_katac(&["reset", "baz", "--files", "../baz"]).failure();
+    run_katac(&["reset", "baz", "--files", "missing.js"]).failure();
+    run_katac(&["reset", "foo"]).failure();
+
+    // names that point outside the day folder are rejected before anything is removed
+    std::fs::write(format!("{}/notes.txt", days_dir), "notes")?;
+    for name in ["..", ".", "../days", "day1/baz", ""] {
+        let cmd = run_katac(&["reset", name]).failure();
+        let stderr = String::from_utf8(cmd.get_output().stderr.clone())?;
+        assert!(stderr.starts_with(&format!("Error: kata name '{}'", name)));
+    }
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(
+            "../../../example_katas/foo",
+            format!("{}/day1/foo", days_dir),
+        )?;
+        let cmd = run_katac(&["reset", "foo"]).failure();
+        let stderr = String::from_utf8(cmd.get_output().stderr.clone())?;
+        assert!(stderr.contains("is not a kata folder of day1"));
+        assert!(std::path::Path::new("tests/example_katas/foo").exists());
+    }
+    assert_eq!(
+        std::fs::read_to_string(format!("{}/notes.txt", days_dir))?,
+        "notes"
+    );
+    assert_eq!(std::fs::read_to_string(&index_js)?, template);
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_diff() -> TestResult {
+    let test_day_folder = format!("{}_diff", DAY_FOLDER);
+    let days_dir = format!("{}/days", test_day_folder);
+    let run_katac = |args: &[&str]| {
+        katac()
+            .args(args)
+            .env("KATAS_DIR", "tests/example_katas")
+            .env("DAYS_DIR", &days_dir)
+            .env(
+                "KATAC_HISTORY",
+                format!("{}/history.jsonl", test_day_folder),
+            )
+            .assert()
+    };
+    run_katac(&["start", "baz"]).code(0);
+    run_katac(&["diff", "baz"])
+        .code(0)
+        .stdout("No changes in baz\n");
+
+    std::fs::write(format!("{}/day1/baz/index.js", days_dir), "day 1\n")?;
+    std::fs::create_dir_all(format!("{}/day1/baz/node_modules", days_dir))?;
+    std::fs::write(format!("{}/day1/baz/node_modules/dep.js", days_dir), "")?;
+    run_katac(&["diff", "baz"]).code(0).stdout(
+        "--- template/baz/index.js\n+++ day1/baz/index.js\n@@ -1 +1 @@\n\
+         -console.log(\"hello world\");\n+day 1\n",
+    );
+
+    run_katac(&["start", "baz"]).code(0);
+    std::fs::write(format!("{}/day2/baz/index.js", days_dir), "day 2\n")?;
+    run_katac(&["diff", "baz", "--from", "1", "--to", "day2"])
+        .code(0)
+        .stdout("--- day1/baz/index.js\n+++ day2/baz/index.js\n@@ -1 +1 @@\n-day 1\n+day 2\n");
+    let cmd = run_katac(&["diff", "baz", "--from", "1", "--color", "always"]).code(0);
+    assert!(String::from_utf8(cmd.get_output().stdout.clone())?.contains("\x1b[32m+day 2\x1b[0m"));
+
+    run_katac(&["diff", "baz", "--from", "3"]).failure();
+    run_katac(&["diff", "foo"]).failure();
+
+    // names that point outside the day and katas folders are rejected
+    for name in ["..", "../..", "day1/baz", ".hidden"] {
+        let cmd = run_katac(&["diff", name]).failure();
+        assert!(cmd.get_output().stdout.is_empty());
+        let stderr = String::from_utf8(cmd.get_output().stderr.clone())?;
+        assert!(stderr.starts_with(&format!("Error: kata name '{}'", name)));
+    }
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_runner_override() -> TestResult {
+    let test_day_folder = format!("{}_runner_override", DAY_FOLDER);
+    let days_dir = format!("{}/days", test_day_folder);
+    let history_file = format!("{}/history.jsonl", test_day_folder);
+    let config = format!("{}/katac.toml", test_day_folder);
+    std::fs::create_dir_all(&test_day_folder)?;
+
+    katac()
+        .args(["baz"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .success();
+    std::fs::write(
+        format!("{}/day1/baz/run.sh", days_dir),
+        "echo from run.sh\n",
+    )?;
+
+    // make comes before run.sh, unless the config file picks the shell runner
+    std::fs::write(&config, "[kata.baz]\nrunner = \"shell\"\n")?;
+    let cmd = katac()
+        .args(["--config", &config, "run", "baz"])
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .success();
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains("from run.sh"));
+    assert!(!output.contains("hello world"));
+
+    std::fs::write(&config, "[kata.baz]\nrunner = \"gradle\"\n")?;
+    let cmd = katac()
+        .args(["--config", &config, "run", "baz"])
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .failure();
+    let stderr = String::from_utf8(cmd.get_output().stderr.clone())?;
+    assert!(stderr.contains("unknown runner 'gradle' for baz in the config file"));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_template_sources() -> TestResult {
+    let test_day_folder = format!("{}_template_sources", DAY_FOLDER);
+    let examples = format!("{}/examples", test_day_folder);
+    let katas_dir = format!("{}/katas", test_day_folder);
+    let days_dir = format!("{}/days", test_day_folder);
+    let history_file = format!("{}/history.jsonl", test_day_folder);
+    std::fs::create_dir_all(format!("{}/go/Stack", examples))?;
+    std::fs::write(format!("{}/go/Stack/stack.go", examples), "package stack\n")?;
+    let status = std::process::Command::new("tar")
+        .args(["czf", "examples.tar.gz", "examples"])
+        .current_dir(&test_day_folder)
+        .status()?;
+    assert!(status.success());
+    let archive = format!("{}/examples.tar.gz", test_day_folder);
+
+    let cmd = katac()
+        .args(["list", "templates", "--source", &archive])
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8(cmd.get_output().stdout.clone())?,
+        "go (1)\n  Stack\n"
+    );
+
+    let cmd = katac()
+        .args(["init", "--select", "Stack", "--source", &archive])
+        .env("KATAS_DIR", &katas_dir)
+        .assert()
+        .success();
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert!(output.contains(&format!("Copied [go] Stack to {}/Stack", katas_dir)));
+    assert!(std::path::Path::new(&format!("{}/Stack/stack.go", katas_dir)).exists());
+
+    // katas that aren't in the katas folder are copied from the source of the config file
+    let config = format!("{}/katac.toml", test_day_folder);
+    std::fs::write(&config, format!("[katas]\nsource = \"{}\"\n", examples))?;
+    katac()
+        .args(["--config", &config, "start", "Stack"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .success()
+        .stdout(format!(
+            "Copying Stack from {}/Stack to day1...\n",
+            examples
+        ));
+    assert!(std::path::Path::new(&format!("{}/day1/Stack/stack.go", days_dir)).exists());
+
+    // or from the source given on the command line, never from the default one
+    katac()
+        .args(["start", "Stack"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .failure()
+        .stderr(
+            "Error: 'Stack' is not in tests/example_katas\n\n\
+             Error: failed to copy 1 kata(s)\n",
+        );
+    katac()
+        .args(["--source", &archive, "start", "Stack"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .success()
+        .stdout(format!("Copying Stack from {}/Stack to day2...\n", archive));
+
+    katac()
+        .args(["--config", &config, "start", "Queue", "--today"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &days_dir)
+        .env("KATAC_HISTORY", &history_file)
+        .assert()
+        .failure()
+        .stderr(format!(
+            "Error: 'Queue' is not in tests/example_katas or in the {} katas\n\n\
+             Error: failed to copy 1 kata(s)\n",
+            examples
+        ));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_user_config() -> TestResult {
+    let test_day_folder = format!("{}_user_config", DAY_FOLDER);
+    let config_home = format!("{}/xdg", test_day_folder);
+    let user_config = format!("{}/katac/config.toml", config_home);
+    let config = format!("{}/katac.toml", test_day_folder);
+    // relative folders of the user config are relative to the folder of the user config
+    let days_dir = format!("{}/katac/days", config_home);
+    let katas_dir = format!("{}/tests/example_katas", env!("CARGO_MANIFEST_DIR"));
+    std::fs::create_dir_all(format!("{}/katac", config_home))?;
+    std::fs::write(
+        &user_config,
+        format!(
+            "[katas]\nkatas_dir = \"{}\"\ndays_dir = \"days\"\ntimeout = 10\n\n[pools.weekday]\nkatas = [\"foo\", \"baz\"]\n",
+            katas_dir
+        ),
+    )?;
+    std::fs::write(
+        &config,
+        "[katas]\ntimeout = 30\n\n[pools.weekday]\nkatas = [\"foo\"]\n",
+    )?;
+
+    // the project config wins over the user config, pools are replaced as a whole
+    katac()
+        .args(["--config", &config, "config", "show"])
+        .env("XDG_CONFIG_HOME", &config_home)
+        .env_remove("KATAS_DIR")
+        .env_remove("DAYS_DIR")
+        .env_remove("KATAC_HISTORY")
+        .assert()
+        .success()
+        .stdout(format!(
+            "katas.day_scheme = \"numbered\"\nkatas.days_dir = \"{}\"\nkatas.history_file = \".katac/history.jsonl\"\nkatas.katas_dir = \"{}\"\nkatas.source = \"embedded\"\nkatas.start_today = false\nkatas.strategy = \"uniform\"\nkatas.timeout = 30\npools.weekday.katas = [\"foo\"]\n",
+            days_dir, katas_dir
+        ));
+
+    let cmd = katac()
+        .args(["--config", &config, "--days-dir", "other_days"])
+        .args(["config", "show", "--origin"])
+        .env("XDG_CONFIG_HOME", &config_home)
+        .env("KATAC_HISTORY", "history.jsonl")
+        .env_remove("KATAS_DIR")
+        .assert()
+        .success();
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let origin = |key: &str| {
+        let line = output.lines().find(|l| l.starts_with(key)).unwrap();
+        line.split("  ")
+            .filter(|c| !c.is_empty())
+            .last()
+            .unwrap()
+            .trim()
+            .to_string()
+    };
+    assert_eq!(origin("katas.days_dir"), "--days-dir");
+    assert_eq!(origin("katas.history_file"), "env KATAC_HISTORY");
+    assert_eq!(
+        origin("katas.katas_dir"),
+        format!("user config {}", user_config)
+    );
+    assert_eq!(
+        origin("katas.timeout"),
+        format!("project config {}", config)
+    );
+    assert_eq!(origin("katas.strategy"), "default");
+
+    // the folders of the user config are used to start a day
+    katac()
+        .args(["--config", &config, "foo"])
+        .env("XDG_CONFIG_HOME", &config_home)
+        .env(
+            "KATAC_HISTORY",
+            format!("{}/history.jsonl", test_day_folder),
+        )
+        .env_remove("KATAS_DIR")
+        .env_remove("DAYS_DIR")
+        .assert()
+        .success()
+        .stdout("Copying foo to day1...\n");
+    assert!(std::path::Path::new(&format!("{}/day1/foo", days_dir)).exists());
+
+    // an invalid user config is reported with its path
+    std::fs::write(&user_config, "[katas]\ntimeout = \"soon\"\n")?;
+    let cmd = katac()
+        .args(["--config", &config, "config", "show"])
+        .env("XDG_CONFIG_HOME", &config_home)
+        .assert()
+        .failure();
+    let stderr = String::from_utf8(cmd.get_output().stderr.clone())?;
+    assert!(stderr.starts_with(&format!("Error: invalid config file {}", user_config)));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_editor() -> TestResult {
+    let test_day_folder = format!("{}_editor", DAY_FOLDER);
+    let config = format!("{}/katac.toml", test_day_folder);
+    std::fs::create_dir_all(&test_day_folder)?;
+    let start = |args: &[&str]| {
+        katac()
+            .args(["--config", &config])
+            .args(args)
+            .env("KATAS_DIR", "tests/example_katas")
+            .env("DAYS_DIR", format!("{}/days", test_day_folder))
+            .env(
+                "KATAC_HISTORY",
+                format!("{}/history.jsonl", test_day_folder),
+            )
+            .assert()
+            .success()
+    };
+
+    // the copied katas are opened with the editor of the config
+    std::fs::write(
+        &config,
+        "[katas]
+editor = \"ls\"\n",
+    )?;
+    start(&["baz"]).stdout("Copying baz to day1...\nMakefile\nindex.js\n");
+    // but not when the output is read by a program
+    let cmd = start(&["--format", "json", "baz"]);
+    assert!(!String::from_utf8(cmd.get_output().stdout.clone())?.contains("index.js"));
+
+    // an editor that can't be started is only a warning
+    std::fs::write(&config, "[katas]\neditor = \"katac-missing-editor\"\n")?;
+    let cmd = start(&["baz"]).stdout("Copying baz to day3...\n");
+    let stderr = String::from_utf8(cmd.get_output().stderr.clone())?;
+    assert!(stderr.starts_with("Warning: failed to open the katas with 'katac-missing-editor'"));
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
+#[test]
+fn test_json_output() -> TestResult {
+    let test_day_folder = format!("{}_json", DAY_FOLDER);
+    let cmd = katac()
+        .args(["--format", "json", "foo", "baz"])
+        .env("KATAS_DIR", "tests/example_katas")
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let copied: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    assert_eq!(copied[0]["kata"], "foo");
+    assert_eq!(copied[1]["kata"], "baz");
+    assert_eq!(copied[1]["day"], 1);
+    assert_eq!(copied[1]["day_name"], "day1");
+    assert!(copied[1]["destination"]
+        .as_str()
+        .unwrap()
+        .ends_with("day1/baz"));
+
+    let cmd = katac()
+        .args(["run", "--format", "json"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let records: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout)?;
+    let records = records.as_array().unwrap();
+    assert_eq!(records.len(), 2);
+    for record in records {
+        match record["kata"].as_str().unwrap() {
+            "foo" => assert_eq!(record["status"], "skipped"),
+            "baz" => {
+                assert_eq!(record["status"], "passed");
+                assert_eq!(record["exit_code"], 0);
+                assert_eq!(record["timed_out"], false);
+                assert_eq!(record["command"], "make run -s");
+                assert!(record["stdout"]
+                    .as_str()
+                    .unwrap()
+                    .contains("console.log(\"hello world\")"));
+            }
+            kata => panic!("unexpected kata {}", kata),
+        }
+    }
+
+    let cmd = katac()
+        .args(["--format", "ndjson", "run", "baz"])
+        .env("DAYS_DIR", &test_day_folder)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 1);
+    let record: serde_json::Value = serde_json::from_str(lines[0])?;
+    assert_eq!(record["kata"], "baz");
+
+    cleanup(&test_day_folder);
+    Ok(())
+}
+
 #[test]
 fn test_new_command() -> TestResult {
     let new_kata_folder = "tests/new_katas";
-    Command::cargo_bin(PRG)?
+    katac()
         .args(["new", "foo2"])
         .env("KATAS_DIR", new_kata_folder)
         .assert()
@@ -220,11 +1445,12 @@ fn test_new_command() -> TestResult {
 
 #[test]
 fn test_new_command_already_exists() -> TestResult {
-    Command::cargo_bin(PRG)?
+    katac()
         .args(["new", "foo"])
         .env("KATAS_DIR", "tests/example_katas")
         .assert()
-        .stdout("Kata foo already exists\n");
+        .failure()
+        .stderr("Error: kata 'foo' already exists in tests/example_katas\n");
     Ok(())
 }
 
@@ -234,7 +1460,7 @@ fn test_init_command() -> TestResult {
 
     // Test with --select flag to bypass interactive mode
     // Using real example-katas directory with language structure
-    let cmd = Command::cargo_bin(PRG)?
+    let cmd = katac()
         .args(["init", "--select", "Queue,Map"])
         .env("KATAS_DIR", &test_katas_dir)
         .args(["--examples-dir", "example-katas"])
@@ -261,7 +1487,7 @@ fn test_init_command_with_duplicates() -> TestResult {
 
     // Select the same kata from multiple languages (if available)
     // This tests the conflict resolution where second selection gets language prefix
-    let cmd = Command::cargo_bin(PRG)?
+    let cmd = katac()
         .args(["init", "--select", "ArrayList"])
         .env("KATAS_DIR", &test_katas_dir)
         .args(["--examples-dir", "example-katas"])
@@ -285,7 +1511,7 @@ fn test_init_command_with_duplicates() -> TestResult {
 #[test]
 fn test_start_command() -> TestResult {
     let test_day_folder = format!("{}_start", DAY_FOLDER);
-    Command::cargo_bin(PRG)?
+    katac()
         .args(["start", "foo", "bar"])
         .env("KATAS_DIR", "tests/example_katas")
         .env("DAYS_DIR", &test_day_folder)
@@ -307,7 +1533,7 @@ fn test_init_creates_makefile() -> TestResult {
     let test_katas_dir = format!("{}_init_makefile", DAY_FOLDER);
 
     // Use init to copy an embedded kata
-    Command::cargo_bin(PRG)?
+    katac()
         .args(["init", "--select", "Queue"])
         .env("KATAS_DIR", &test_katas_dir)
         .assert()
@@ -341,9 +1567,7 @@ fn test_upgrade_help_advertises_force_flag() -> TestResult {
     // Smoke test for the upgrade subcommand wiring — confirms the --force
     // flag is reachable through clap without hitting the network or
     // replacing the running binary.
-    let cmd = Command::cargo_bin(PRG)?
-        .args(["upgrade", "--help"])
-        .assert();
+    let cmd = katac().args(["upgrade", "--help"]).assert();
     let output = String::from_utf8(cmd.get_output().stdout.clone())?;
     assert!(output.contains("--force"));
     assert!(output.contains("Force reinstallation"));
@@ -356,7 +1580,7 @@ fn test_copy_creates_makefile() -> TestResult {
     let test_day_folder = format!("{}_copy_makefile_days", DAY_FOLDER);
 
     // First, init an embedded kata into katas dir
-    Command::cargo_bin(PRG)?
+    katac()
         .args(["init", "--select", "Map"])
         .env("KATAS_DIR", &test_katas_dir)
         .assert()
@@ -381,7 +1605,7 @@ fn test_copy_creates_makefile() -> TestResult {
     }
 
     // Now copy to a day folder - it should recreate the Makefile
-    Command::cargo_bin(PRG)?
+    katac()
         .args([map_name])
         .env("KATAS_DIR", &test_katas_dir)
         .env("DAYS_DIR", &test_day_folder)